@@ -0,0 +1,103 @@
+use std::{fs::OpenOptions, io::Write, sync::Mutex};
+
+use serde_json::json;
+
+use crate::{queue, utils};
+
+// Instances are processed concurrently, rows are appended one at a time.
+static FILE: Mutex<()> = Mutex::new(());
+
+// Creates the "AUDIT_FILE" right away, a path Swaparr can't write to is reported before the first run.
+pub fn init(env: &utils::system::Envs) {
+    if env.audit_file.is_empty() {
+        return;
+    }
+
+    if let Err(error) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&env.audit_file)
+    {
+        utils::log::alert(
+            "FATAL",
+            "Environment variable \"AUDIT_FILE\" is not valid.",
+            "It must be a path Swaparr is allowed to write to.",
+            Some(error.to_string()),
+        );
+        utils::system::exit(1);
+    }
+}
+
+// Quotes a CSV field, names may contain commas or quotes themselves.
+fn csv_field(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+// Appends an action to the "AUDIT_FILE", if the action is one of the "AUDIT_EVENTS".
+pub fn record(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    action: &str,
+    download: &queue::Download,
+    strikes: u32,
+) {
+    if env.audit_file.is_empty() || !env.audit_events.iter().any(|event| event == action) {
+        return;
+    }
+
+    let timestamp = utils::log::timestamp();
+
+    let row = if env.audit_format == "jsonl" {
+        json!({
+            "timestamp": timestamp,
+            "platform": instance.platform,
+            "instance": instance.id,
+            "id": download.id,
+            "name": download.name,
+            "size_bytes": download.size,
+            "eta_ms": download.eta,
+            "strikes": strikes,
+            "action": action,
+        })
+        .to_string()
+    } else {
+        [
+            timestamp,
+            instance.platform.clone(),
+            instance.id.clone(),
+            download.id.to_string(),
+            csv_field(&download.name),
+            download.size.to_string(),
+            download.eta.to_string(),
+            strikes.to_string(),
+            action.to_string(),
+        ]
+        .join(",")
+    };
+
+    let _lock = FILE.lock().unwrap_or_else(|error| error.into_inner());
+
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&env.audit_file)
+        .and_then(|mut file| {
+            // A new CSV file starts with its header.
+            if env.audit_format == "csv" && file.metadata()?.len() == 0 {
+                writeln!(
+                    file,
+                    "timestamp,platform,instance,id,name,size,eta,strikes,action"
+                )?;
+            }
+            writeln!(file, "{row}")
+        });
+
+    if let Err(error) = result {
+        utils::log::alert(
+            "WARN",
+            "Failed to write to the \"AUDIT_FILE\".",
+            "The action itself was not affected by this.",
+            Some(error.to_string()),
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use std::{collections::VecDeque, sync::Mutex};
+
+use crate::{libs, queue, utils};
+
+// Removals listed on the dashboard, older ones are dropped.
+const RECENT_REMOVALS: usize = 50;
+
+// Everything the dashboard shows, replaced after every run.
+struct State {
+    config: String,
+    last_run: String,
+    instances: Vec<(String, Vec<libs::table::TableContent>)>,
+    // Timestamp, instance, name and size of the latest removals, newest first.
+    removals: VecDeque<(String, String, String, String)>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    config: String::new(),
+    last_run: String::new(),
+    instances: Vec::new(),
+    removals: VecDeque::new(),
+});
+
+// Names come from release titles, those must never be interpreted as HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Stores the outcome of a run, the dashboard shows it until the next run.
+pub fn update(env: &utils::system::Envs, summaries: &[queue::Summary]) {
+    if env.web_port.is_none_or(|port| port == 0) {
+        return;
+    }
+
+    let mut state = STATE.lock().unwrap_or_else(|error| error.into_inner());
+    let timestamp = utils::log::timestamp();
+
+    state.instances = env
+        .instances
+        .iter()
+        .zip(summaries)
+        .map(|(instance, summary)| (instance.id.clone(), summary.table_contents.clone()))
+        .collect();
+
+    for (instance, summary) in env.instances.iter().zip(summaries) {
+        for content in summary
+            .removed_rows
+            .iter()
+            .map(|row| &summary.table_contents[*row])
+        {
+            state.removals.push_front((
+                timestamp.clone(),
+                instance.id.clone(),
+                content.name.clone(),
+                content.size.clone(),
+            ));
+        }
+    }
+    state.removals.truncate(RECENT_REMOVALS);
+
+    state.last_run = timestamp;
+}
+
+// Renders the dashboard as a single page, refreshed by the browser every 30 seconds.
+fn render() -> String {
+    let state = STATE.lock().unwrap_or_else(|error| error.into_inner());
+
+    let mut body = String::from(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><meta http-equiv=\"refresh\" content=\"30\">\
+         <title>Swaparr</title><style>body{font-family:sans-serif;margin:2em}\
+         table{border-collapse:collapse;margin-bottom:2em}td,th{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+         pre{background:#f4f4f4;padding:1em}</style></head><body><h1>Swaparr</h1>",
+    );
+
+    body.push_str(&format!(
+        "<p>Last run: {}</p>",
+        if state.last_run.is_empty() {
+            "not yet"
+        } else {
+            &state.last_run
+        }
+    ));
+
+    for (instance, contents) in &state.instances {
+        body.push_str(&format!(
+            "<h2>Queue of {}</h2><table><tr><th>Strikes</th><th>State</th><th>Name</th><th>Size</th><th>Progress</th><th>ETA</th><th>Reason</th></tr>",
+            escape(instance)
+        ));
+        for content in contents {
+            body.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&content.strikes),
+                escape(&content.state),
+                escape(&content.name),
+                escape(&content.size),
+                escape(&content.progress),
+                escape(&content.eta),
+                escape(&content.reason),
+            ));
+        }
+        body.push_str("</table>");
+    }
+
+    body.push_str("<h2>Recent removals</h2><table><tr><th>Time</th><th>Instance</th><th>Name</th><th>Size</th></tr>");
+    for (timestamp, instance, name, size) in &state.removals {
+        body.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(timestamp),
+            escape(instance),
+            escape(name),
+            escape(size),
+        ));
+    }
+    body.push_str("</table>");
+
+    body.push_str(&format!(
+        "<h2>Configuration</h2><pre>{}</pre></body></html>",
+        escape(&state.config)
+    ));
+
+    body
+}
+
+// Starts the read-only dashboard on a background thread.
+pub fn serve(env: &utils::system::Envs) {
+    STATE
+        .lock()
+        .unwrap_or_else(|error| error.into_inner())
+        .config = env.redacted();
+
+    libs::server::serve(
+        "dashboard",
+        env.bind_address.unwrap(),
+        env.web_port.unwrap(),
+        |path| match path {
+            "/" => libs::server::Response {
+                status: 200,
+                content_type: "text/html; charset=utf-8",
+                body: render(),
+            },
+            _ => libs::server::Response {
+                status: 404,
+                content_type: "text/plain",
+                body: String::from("Not Found"),
+            },
+        },
+    );
+}
@@ -0,0 +1,39 @@
+use std::{collections::BTreeMap, net::IpAddr, sync::Mutex};
+
+use serde_json::json;
+
+use crate::{libs, utils};
+
+// Instance id mapped onto whether its latest API call succeeded.
+static REACHABLE: Mutex<BTreeMap<String, bool>> = Mutex::new(BTreeMap::new());
+
+// Records the outcome of the latest API call towards an instance.
+pub fn report(instance: &utils::system::Instance, reachable: bool) {
+    if let Ok(mut instances) = REACHABLE.lock() {
+        instances.insert(instance.id.clone(), reachable);
+    }
+}
+
+// Starts the "/healthz" endpoint on a background thread.
+pub fn serve(address: IpAddr, port: u16) {
+    libs::server::serve("health", address, port, |path| match path {
+        "/healthz" => {
+            let instances = match REACHABLE.lock() {
+                Ok(instances) => instances.clone(),
+                Err(_) => BTreeMap::new(),
+            };
+            let healthy = !instances.is_empty() && instances.values().all(|reachable| *reachable);
+
+            libs::server::Response {
+                status: if healthy { 200 } else { 503 },
+                content_type: "application/json",
+                body: json!({ "healthy": healthy, "instances": instances }).to_string(),
+            }
+        }
+        _ => libs::server::Response {
+            status: 404,
+            content_type: "text/plain",
+            body: String::from("Not Found"),
+        },
+    });
+}
@@ -0,0 +1,185 @@
+use std::{fs, thread::sleep, time::Duration};
+
+use reqwest::{
+    blocking::{Client, ClientBuilder, RequestBuilder, Response},
+    header::RETRY_AFTER,
+    Certificate, StatusCode,
+};
+
+use crate::utils;
+
+// The starr API and every other service are reached through a client of their own, so
+// "INSECURE_SKIP_VERIFY" never exposes the notifier tokens or the qBittorrent and Prowlarr credentials.
+pub struct Clients {
+    pub starr: Client,
+    pub services: Client,
+}
+
+// Builds the HTTP clients shared by all requests.
+pub fn clients(env: &utils::system::Envs) -> Clients {
+    let mut starr = builder(env);
+
+    if env.insecure_skip_verify == "true" {
+        utils::log::alert(
+            "WARN",
+            "Certificate verification is disabled.",
+            "Any certificate of the starr API is accepted, connections are open to man-in-the-middle attacks.",
+            None,
+        );
+        starr = starr.danger_accept_invalid_certs(true);
+    }
+
+    Clients {
+        starr: build(starr),
+        services: build(builder(env)),
+    }
+}
+
+fn builder(env: &utils::system::Envs) -> ClientBuilder {
+    // Identifies Swaparr in the access logs of the starr instances, e.g. "swaparr/1.0.0".
+    let builder = Client::builder()
+        .timeout(Duration::from_secs(env.request_timeout))
+        .user_agent(&env.user_agent);
+
+    // Trusts a custom CA, e.g. for a starr instance behind a proxy with a self-signed certificate.
+    if env.ca_cert_path.is_empty() {
+        return builder;
+    }
+
+    match fs::read(&env.ca_cert_path)
+        .map_err(|error| error.to_string())
+        .and_then(|pem| Certificate::from_pem(&pem).map_err(|error| error.to_string()))
+    {
+        Ok(certificate) => builder.add_root_certificate(certificate),
+        Err(error) => {
+            utils::log::alert(
+                "FATAL",
+                "Environment variable \"CA_CERT_PATH\" is not valid.",
+                "Must be the path to a PEM encoded certificate.",
+                Some(error),
+            );
+            utils::system::exit(1);
+        }
+    }
+}
+
+fn build(builder: ClientBuilder) -> Client {
+    match builder.build() {
+        Ok(client) => client,
+        Err(error) => {
+            utils::log::alert(
+                "FATAL",
+                "Unable to initialize the HTTP client.",
+                "Check the request related environment variables and try again.",
+                Some(error.to_string()),
+            );
+            utils::system::exit(1);
+        }
+    }
+}
+
+// Attaches the API key to a request, as a header unless "APIKEY_IN_QUERY" is set.
+pub fn authorize(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    request: RequestBuilder,
+) -> RequestBuilder {
+    if env.apikey_in_query == "true" {
+        request.query(&[("apikey", &instance.apikey)])
+    } else {
+        request.header("X-Api-Key", &instance.apikey)
+    }
+}
+
+// Rate limited requests are attempted this many times more, before the response is returned as is.
+const RATE_LIMIT_RETRIES: u32 = 3;
+
+// Waits longer than this are cut short, a run should never hang on a misbehaving proxy.
+const RATE_LIMIT_MAX_WAIT: Duration = Duration::from_secs(300);
+
+// Time to wait as asked by "Retry-After", either in seconds or as a date; 5 seconds when absent.
+fn retry_after(res: &Response) -> Duration {
+    let retry_after = res
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    let wait = retry_after
+        .trim()
+        .parse::<u64>()
+        .map(Duration::from_secs)
+        .ok()
+        .or_else(|| {
+            chrono::DateTime::parse_from_rfc2822(retry_after)
+                .ok()
+                .and_then(|date| (date.to_utc() - chrono::Utc::now()).to_std().ok())
+        })
+        .unwrap_or(Duration::from_secs(5));
+
+    wait.min(RATE_LIMIT_MAX_WAIT)
+}
+
+// Sends a request, a "429 Too Many Requests" is waited out for as long as the API asks.
+pub fn send(request: RequestBuilder) -> reqwest::Result<Response> {
+    let mut request = request;
+    let mut attempt: u32 = 0;
+
+    loop {
+        let retry = request.try_clone();
+        let res = request.send()?;
+
+        let retry = match retry {
+            Some(retry)
+                if res.status() == StatusCode::TOO_MANY_REQUESTS
+                    && attempt < RATE_LIMIT_RETRIES =>
+            {
+                retry
+            }
+            _ => return Ok(res),
+        };
+
+        let wait = retry_after(&res);
+        utils::log::alert(
+            "WARN",
+            "The API is rate limiting requests, waiting before trying again.",
+            format!(
+                "Retrying in {}, as asked by \"Retry-After\".",
+                humantime::format_duration(wait)
+            )
+            .as_str(),
+            None,
+        );
+
+        sleep(wait);
+        request = retry;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_key_as_header_or_query() {
+        let mut env = utils::system::test_env();
+        let instance = utils::system::test_instance("radarr", "http://host/radarr");
+        let url = utils::parse::deleteapi(&env, &instance, &[12]);
+
+        let request = authorize(&env, &instance, Client::new().delete(&url))
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()["X-Api-Key"], "test");
+        assert_eq!(request.url().as_str(), url);
+
+        // The query keeps the removal settings, the API key is appended to them.
+        env.apikey_in_query = String::from("true");
+        let request = authorize(&env, &instance, Client::new().delete(&url))
+            .build()
+            .unwrap();
+        assert!(!request.headers().contains_key("X-Api-Key"));
+        assert_eq!(request.url().as_str(), format!("{url}&apikey=test"));
+        assert_eq!(request.url().path(), "/radarr/api/v3/queue/12");
+    }
+}
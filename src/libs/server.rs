@@ -0,0 +1,68 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{IpAddr, TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use crate::utils;
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+// Serves plain HTTP on a background thread, enough for scrapers and probes.
+pub fn serve(name: &'static str, address: IpAddr, port: u16, handler: fn(&str) -> Response) {
+    let listener = TcpListener::bind((address, port)).unwrap_or_else(|error| {
+        utils::log::alert(
+            "FATAL",
+            format!("Unable to start the {name} server on {address} port {port}.").as_str(),
+            "Ensure that the port is not in use and the address belongs to this host.",
+            Some(error.to_string()),
+        );
+        utils::system::exit(1);
+    });
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            respond(stream, handler);
+        }
+    });
+}
+
+// Answers a single request, the connection is closed afterwards.
+fn respond(stream: TcpStream, handler: fn(&str) -> Response) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let mut reader = BufReader::new(&stream);
+
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+
+    // Drain the headers, the path is all that matters.
+    let mut header = String::new();
+    while reader.read_line(&mut header).is_ok_and(|read| read > 2) {
+        header.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let response = handler(path);
+
+    let _ = write!(
+        &stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        match response.status {
+            200 => "OK",
+            404 => "Not Found",
+            503 => "Service Unavailable",
+            _ => "Unknown",
+        },
+        response.content_type,
+        response.body.len(),
+        response.body
+    );
+}
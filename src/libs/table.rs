@@ -2,20 +2,23 @@ use tabled::{settings::Style, Table, Tabled};
 
 use crate::utils;
 
-#[derive(Tabled)]
+#[derive(Tabled, Clone)]
 pub struct TableContent {
     pub strikes: String,
+    #[tabled(display_with = "utils::log::state")]
     pub state: String,
     pub name: String,
     pub size: String,
+    pub progress: String,
     pub eta: String,
+    pub reason: String,
 }
 
 pub fn render(contents: &Vec<TableContent>) {
-    if contents.len() > 0 {
+    if !contents.is_empty() {
         let mut table = Table::new(contents);
         table.with(Style::rounded());
-        println!("{}", table.to_string())
+        utils::log::print(&format!("{}\n", table));
     } else {
         utils::log::empty();
     }
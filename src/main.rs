@@ -1,40 +1,219 @@
-use std::{collections::HashMap, thread::sleep, time::Duration};
+use std::{
+    collections::HashMap,
+    thread,
+    time::{Duration, Instant},
+};
 
+mod audit;
+mod dashboard;
+mod health;
 mod libs;
+mod metrics;
+mod notify;
+mod prowlarr;
+mod qbittorrent;
 mod queue;
+mod report;
+mod status;
 mod tests;
 mod utils;
 
 fn main() {
+    // Checked before anything else, no configuration is required to print the version.
+    if std::env::args().any(|arg| arg == "--version" || arg == "-V") {
+        println!("swaparr {}", env!("CARGO_PKG_VERSION"));
+        return;
+    }
+
     let env = utils::system::env();
-    let baseapi = utils::parse::baseapi(&env.platform, &env.baseurl);
-    let queueapi = utils::parse::queueapi(&env.platform, &baseapi, &env.apikey);
+    audit::init(&env);
+    status::init(&env);
+    let clients = libs::http::clients(&env);
 
     // Test: Health-checks
-    tests::api::test(&env.platform, &baseapi, &env.apikey);
+    for instance in &env.instances {
+        tests::api::test(&env, instance, &clients.starr);
+    }
+
+    // A shutdown signal lets the current run finish, instead of interrupting a removal.
+    // Listened for once the health checks pass, a signal while they retry stops Swaparr right away.
+    let shutdown = utils::system::shutdown();
+
+    // Validated by "problems()", an invalid address never gets this far.
+    let bind_address = env.bind_address.unwrap();
+
+    if let Some(port) = env.metrics_port.filter(|port| *port != 0) {
+        metrics::serve(bind_address, port);
+    }
+
+    if let Some(port) = env.health_port.filter(|port| *port != 0) {
+        health::serve(bind_address, port);
+    }
+
+    if env.web_port.is_some_and(|port| port != 0) {
+        dashboard::serve(&env);
+    }
 
     // Displays initial "banner" with set configurations.
     utils::log::banner(&env);
 
-    let mut strikelist: HashMap<u32, u32> = HashMap::new();
+    // Included in every log, so support requests show what is actually running.
+    utils::log::alert(
+        "INFO",
+        &format!("Swaparr v{} has started.", env!("CARGO_PKG_VERSION")),
+        "Resolved configuration, secrets are masked:",
+        Some(env.redacted()),
+    );
+
+    // Every instance keeps track of its own downloads, in the same order as the instances.
+    let mut strikelists: Vec<HashMap<u32, queue::Tracker>> =
+        env.instances.iter().map(|_| HashMap::new()).collect();
+
+    // Size of every download removed since Swaparr started, in the same order as the instances.
+    let mut reclaimed: Vec<u64> = env.instances.iter().map(|_| 0).collect();
+
+    // What would have been removed in dry-run mode, printed once Swaparr exits.
+    let mut report = report::Report::default();
+
+    // Runs since starting, the first "WARMUP_CYCLES" of them never remove a download.
+    let mut runs: u32 = 0;
 
     loop {
-        let queue_items = queue::get(&env.platform, &queueapi);
+        let started = Instant::now();
+        let cycle = queue::Cycle::new(&env, &clients, runs < env.warmup_cycles);
+        runs += 1;
+
+        // Instances are processed concurrently, a slow API won't delay the others.
+        let summaries: Vec<queue::Summary> = thread::scope(|scope| {
+            let handles: Vec<_> = env
+                .instances
+                .iter()
+                .zip(strikelists.iter_mut())
+                .map(|(instance, strikelist)| {
+                    let (env, clients, cycle) = (&env, &clients, &cycle);
+                    scope.spawn(move || queue::run(env, instance, clients, strikelist, cycle))
+                })
+                .collect();
 
-        // Cleanup downloads tracker
-        strikelist.retain(|&k, _| queue_items.iter().any(|item| item.id == k));
+            // A panicking instance counts as a failed run, the other instances are unaffected.
+            handles
+                .into_iter()
+                .zip(&env.instances)
+                .map(|(handle, instance)| {
+                    handle.join().unwrap_or_else(|_| {
+                        utils::log::alert(
+                            "ERROR",
+                            "Processing the queue panicked, will attempt again next run.",
+                            &instance.id,
+                            None,
+                        );
+                        queue::Summary {
+                            failed: true,
+                            ..Default::default()
+                        }
+                    })
+                })
+                .collect()
+        });
 
-        // Process downloads - Also prints table to logs
-        queue::process(&env, &baseapi, queue_items, &mut strikelist);
+        // Space freed by the removals of this run, logged along with the total since starting.
+        let reclaimed_run: u64 = summaries.iter().map(|summary| summary.reclaimed).sum();
+        for (total, summary) in reclaimed.iter_mut().zip(&summaries) {
+            *total += summary.reclaimed;
+        }
 
-        println!(" ─ Checking again in {}..\n", &env.scan_interval);
+        status::write(&env, &summaries, &strikelists, &reclaimed);
+        dashboard::update(&env, &summaries);
 
-        // Thread sleeper
-        sleep(Duration::from_millis(
+        for (instance, summary) in env.instances.iter().zip(&summaries) {
+            report.add(instance, summary);
+        }
+
+        // Quiet mode only logs removals, those are alerted while processing.
+        let quiet = env.quiet == "true";
+
+        if !quiet {
+            for (instance, summary) in env.instances.iter().zip(summaries) {
+                if env.instances.len() > 1 {
+                    utils::log::print(&format!(" ─ Instance: {}\n", &instance.id));
+                }
+
+                if summary.failed {
+                    utils::log::print(
+                        " ─ Queue: unavailable, the strikes are kept until the next run.\n",
+                    );
+                    continue;
+                }
+
+                libs::table::render(&summary.table_contents);
+
+                utils::log::print(&format!(
+                    " ─ Queue: {} total, {} striked, {} removed, {} ignored, {} queued.\n",
+                    summary.total,
+                    summary.striked,
+                    summary.removed,
+                    summary.ignored,
+                    summary.queued
+                ));
+            }
+
+            if reclaimed_run > 0 {
+                utils::log::print(&format!(
+                    " ─ Reclaimed {} this run ({} total).\n",
+                    utils::parse::bytes_to_size_string(&reclaimed_run, &env.size_units),
+                    utils::parse::bytes_to_size_string(&reclaimed.iter().sum(), &env.size_units)
+                ));
+            }
+
+            if &env.dry_run == "true" {
+                utils::log::print(" ─ Dry-run mode enabled, no actions will be taken.\n");
+            }
+
+            if cycle.warmup {
+                utils::log::print(&format!(
+                    " ─ Warmup: run {} of {}, no downloads will be removed yet.\n",
+                    runs, env.warmup_cycles
+                ));
+            }
+        }
+
+        // Rounded to milliseconds, to keep the log readable.
+        let elapsed = Duration::from_millis(started.elapsed().as_millis() as u64);
+
+        // A single cycle is enough when scheduled externally, e.g. by cron.
+        if &env.run_once == "true" {
+            if !quiet {
+                utils::log::print(&format!(
+                    " ─ Finished in {}, exiting as run-once mode is enabled.\n\n",
+                    humantime::format_duration(elapsed)
+                ));
+            }
+            break;
+        }
+
+        if !quiet {
+            utils::log::print(&format!(
+                " ─ Finished in {}, checking again in {}..\n\n",
+                humantime::format_duration(elapsed),
+                &env.scan_interval
+            ));
+        }
+
+        // Thread sleeper, woken up early by a shutdown signal.
+        let scan_interval = Duration::from_millis(
             match utils::parse::string_time_notation_to_ms(&env.scan_interval) {
                 Ok(scan_interval_ms) => scan_interval_ms as u64,
                 Err(_) => 10 * 60 * 1000,
             },
-        ));
+        );
+
+        if shutdown.recv_timeout(scan_interval).is_ok() {
+            utils::log::print(" ─ Received a shutdown signal, exiting..\n\n");
+            break;
+        }
+    }
+
+    if &env.dry_run == "true" {
+        report.print(&env);
     }
 }
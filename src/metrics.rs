@@ -0,0 +1,98 @@
+use std::{collections::BTreeMap, net::IpAddr, sync::Mutex};
+
+use crate::{libs, utils};
+
+// Metric name, platform and instance mapped onto the current value.
+static METRICS: Mutex<BTreeMap<(&str, String, String), u64>> = Mutex::new(BTreeMap::new());
+
+const DESCRIPTIONS: [(&str, &str, &str); 5] = [
+    (
+        "swaparr_torrents_removed_total",
+        "counter",
+        "Downloads removed from the starr instance.",
+    ),
+    (
+        "swaparr_reclaimed_bytes_total",
+        "counter",
+        "Size of the downloads removed from the starr instance.",
+    ),
+    (
+        "swaparr_torrents_striked_total",
+        "counter",
+        "Strikes handed out to downloads.",
+    ),
+    (
+        "swaparr_queue_size",
+        "gauge",
+        "Downloads in the queue during the last run.",
+    ),
+    (
+        "swaparr_api_errors_total",
+        "counter",
+        "Failed requests towards the starr API.",
+    ),
+];
+
+// Increases a counter of the instance by one.
+pub fn increment(name: &'static str, instance: &utils::system::Instance) {
+    add(name, instance, 1);
+}
+
+// Increases a counter of the instance by the given value.
+pub fn add(name: &'static str, instance: &utils::system::Instance, value: u64) {
+    if let Ok(mut metrics) = METRICS.lock() {
+        *metrics
+            .entry((name, instance.platform.clone(), instance.id.clone()))
+            .or_default() += value;
+    }
+}
+
+// Overwrites a gauge of the instance.
+pub fn set(name: &'static str, instance: &utils::system::Instance, value: u64) {
+    if let Ok(mut metrics) = METRICS.lock() {
+        metrics.insert(
+            (name, instance.platform.clone(), instance.id.clone()),
+            value,
+        );
+    }
+}
+
+// Renders all metrics in the Prometheus text exposition format.
+fn render() -> String {
+    let metrics = match METRICS.lock() {
+        Ok(metrics) => metrics,
+        Err(_) => return String::new(),
+    };
+
+    let mut body = String::new();
+
+    for (name, kind, description) in DESCRIPTIONS {
+        body.push_str(&format!(
+            "# HELP {name} {description}\n# TYPE {name} {kind}\n"
+        ));
+
+        for ((_, platform, instance), value) in metrics.iter().filter(|((n, _, _), _)| *n == name) {
+            body.push_str(&format!(
+                "{name}{{platform=\"{platform}\",instance=\"{instance}\"}} {value}\n"
+            ));
+        }
+    }
+
+    body
+}
+
+// Starts the "/metrics" endpoint on a background thread.
+pub fn serve(address: IpAddr, port: u16) {
+    libs::server::serve("metrics", address, port, |path| match path {
+        "/metrics" => libs::server::Response {
+            status: 200,
+            content_type: "text/plain; version=0.0.4",
+            body: render(),
+        },
+        _ => libs::server::Response {
+            status: 404,
+            content_type: "text/plain",
+            body: String::from("Not Found"),
+        },
+    });
+}
@@ -0,0 +1,27 @@
+use reqwest::blocking::{Client, RequestBuilder};
+use serde_json::json;
+
+use crate::{queue, utils};
+
+// Builds a Discord webhook request with an embed describing the removed download.
+pub fn removed(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    client: &Client,
+    download: &queue::Download,
+    strikes: u32,
+) -> RequestBuilder {
+    client.post(&env.discord_webhook).json(&json!({
+        "embeds": [{
+            "title": "Download removed",
+            "description": download.name,
+            "color": 0xED4245,
+            "fields": [
+                { "name": "Size", "value": utils::parse::bytes_to_size_string(&download.size, &env.size_units), "inline": true },
+                { "name": "ETA", "value": utils::parse::ms_to_eta_string(&download.eta), "inline": true },
+                { "name": "Strikes", "value": format!("{}/{}", strikes, env.max_strikes), "inline": true },
+            ],
+            "footer": { "text": format!("Swaparr ─ {}", instance.platform) },
+        }],
+    }))
+}
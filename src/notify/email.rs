@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, Message, SmtpTransport,
+    Transport,
+};
+
+use crate::{queue, utils};
+
+// Sends a single email listing every download that was removed from the instance this run.
+pub fn removed(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    removals: &[(&queue::Download, u32)],
+) -> Result<(), String> {
+    let mut message = Message::builder()
+        .from(
+            env.smtp_from
+                .parse::<Mailbox>()
+                .map_err(|error| error.to_string())?,
+        )
+        .subject(format!(
+            "Swaparr removed {} download(s) from {}",
+            removals.len(),
+            instance.id
+        ));
+
+    for to in &env.smtp_to {
+        message = message.to(to.parse::<Mailbox>().map_err(|error| error.to_string())?);
+    }
+
+    let body = removals
+        .iter()
+        .map(|(download, strikes)| {
+            format!(
+                "{}\nSize: {}\nStrikes: {}/{}\n",
+                download.name,
+                utils::parse::bytes_to_size_string(&download.size, &env.size_units),
+                strikes,
+                env.max_strikes,
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let message = message.body(body).map_err(|error| error.to_string())?;
+
+    // Port 465 expects TLS right away, the other ports upgrade the connection with STARTTLS.
+    let mut transport = if env.smtp_port == Some(465) {
+        SmtpTransport::relay(&env.smtp_host)
+    } else {
+        SmtpTransport::starttls_relay(&env.smtp_host)
+    }
+    .map_err(|error| error.to_string())?
+    .port(env.smtp_port.unwrap())
+    .timeout(Some(Duration::from_secs(env.request_timeout)));
+
+    if !env.smtp_user.is_empty() {
+        transport = transport.credentials(Credentials::new(
+            env.smtp_user.clone(),
+            env.smtp_pass.clone(),
+        ));
+    }
+
+    transport
+        .build()
+        .send(&message)
+        .map(|_| ())
+        .map_err(|error| error.to_string())
+}
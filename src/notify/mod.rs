@@ -0,0 +1,171 @@
+use reqwest::blocking::{Client, RequestBuilder, Response};
+
+use crate::{queue, utils};
+
+pub mod discord;
+pub mod email;
+pub mod ntfy;
+pub mod pushover;
+pub mod slack;
+pub mod telegram;
+pub mod webhook;
+
+// Notifies every configured service about a removed download.
+pub fn removed(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    client: &Client,
+    download: &queue::Download,
+    strikes: u32,
+) {
+    if !env.discord_webhook.is_empty() {
+        send(
+            "Discord",
+            "removed",
+            discord::removed(env, instance, client, download, strikes),
+        );
+    }
+
+    if !env.telegram_bot_token.is_empty() && !env.telegram_chat_id.is_empty() {
+        send(
+            "Telegram",
+            "removed",
+            telegram::removed(env, instance, client, download, strikes),
+        );
+    }
+
+    if !env.pushover_token.is_empty() && !env.pushover_user.is_empty() {
+        send(
+            "Pushover",
+            "removed",
+            pushover::removed(env, instance, client, download),
+        );
+    }
+
+    if !env.ntfy_url.is_empty() {
+        send(
+            "ntfy",
+            "removed",
+            ntfy::removed(env, instance, client, download, strikes),
+        );
+    }
+
+    if !env.webhook_url.is_empty() && env.webhook_events.iter().any(|e| e == "removed") {
+        send(
+            "webhook",
+            "removed",
+            webhook::event(env, instance, client, "removed", download, strikes),
+        );
+    }
+}
+
+// Notifies services that batch every removal of a run, e.g. a single email.
+pub fn removals(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    client: &Client,
+    removals: &[(&queue::Download, u32)],
+) {
+    if removals.is_empty() {
+        return;
+    }
+
+    if !env.slack_webhook.is_empty() {
+        for batch in removals.chunks(slack::MAX_DOWNLOADS) {
+            send(
+                "Slack",
+                "removed",
+                slack::removed(env, instance, client, batch),
+            );
+        }
+    }
+
+    if !env.smtp_host.is_empty() && !env.smtp_to.is_empty() {
+        if let Err(error) = email::removed(env, instance, removals) {
+            utils::log::alert(
+                "WARN",
+                "Failed to send email notification.",
+                unaffected("removed"),
+                Some(error),
+            );
+        }
+    }
+}
+
+// Notifies every configured service about a striked download.
+pub fn striked(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    client: &Client,
+    download: &queue::Download,
+    strikes: u32,
+) {
+    if !env.webhook_url.is_empty() && env.webhook_events.iter().any(|e| e == "striked") {
+        send(
+            "webhook",
+            "striked",
+            webhook::event(env, instance, client, "striked", download, strikes),
+        );
+    }
+}
+
+// Sends a notification about the event, failures are logged but never interrupt the run.
+fn send(service: &str, event: &str, request: RequestBuilder) {
+    let Some(error) = failure(request.send()) else {
+        return;
+    };
+
+    utils::log::alert(
+        "WARN",
+        format!("Failed to send {service} notification.").as_str(),
+        unaffected(event),
+        Some(error),
+    );
+}
+
+// Reassures that only the notification failed, worded after the event it was about.
+fn unaffected(event: &str) -> &'static str {
+    match event {
+        "striked" => "The strike itself was not affected by this.",
+        _ => "The removal itself was not affected by this.",
+    }
+}
+
+// Why a notification failed, leaving out the URL as it carries the bot token or webhook secret.
+fn failure(result: reqwest::Result<Response>) -> Option<String> {
+    match result {
+        Ok(res) if res.status().is_success() => None,
+        Ok(res) => Some(format!(
+            "The API has responded with status \"{}\".",
+            res.status()
+        )),
+        Err(error) => Some(error.without_url().to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failure_hides_the_url() {
+        let result = Client::new()
+            .get("http://127.0.0.1:9/bot123456:SECRET/sendMessage")
+            .send();
+
+        let error = failure(result).unwrap();
+        assert!(!error.contains("SECRET"), "{error}");
+    }
+
+    #[test]
+    fn failures_name_the_event() {
+        assert_eq!(
+            unaffected("striked"),
+            "The strike itself was not affected by this."
+        );
+        assert_eq!(
+            unaffected("removed"),
+            "The removal itself was not affected by this."
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use reqwest::blocking::{Client, RequestBuilder};
+
+use crate::{queue, utils};
+
+// Builds an ntfy publish request with a message describing the removed download.
+pub fn removed(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    client: &Client,
+    download: &queue::Download,
+    strikes: u32,
+) -> RequestBuilder {
+    // The topic is either part of "NTFY_URL" or set separately through "NTFY_TOPIC".
+    let url = if env.ntfy_topic.is_empty() {
+        env.ntfy_url.clone()
+    } else {
+        format!("{}/{}", env.ntfy_url.trim_end_matches('/'), env.ntfy_topic)
+    };
+
+    let request = client
+        .post(url)
+        .header(
+            "Title",
+            format!("Download removed by Swaparr ({})", instance.platform),
+        )
+        .header("Priority", "default")
+        .header("Tags", "wastebasket")
+        .body(format!(
+            "{}\nSize: {}\nStrikes: {}/{}",
+            download.name,
+            utils::parse::bytes_to_size_string(&download.size, &env.size_units),
+            strikes,
+            env.max_strikes,
+        ));
+
+    if env.ntfy_token.is_empty() {
+        request
+    } else {
+        request.bearer_auth(&env.ntfy_token)
+    }
+}
@@ -0,0 +1,25 @@
+use reqwest::blocking::{Client, RequestBuilder};
+use serde_json::json;
+
+use crate::{queue, utils};
+
+// Builds a Pushover API request with a concise message about the removed download.
+pub fn removed(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    client: &Client,
+    download: &queue::Download,
+) -> RequestBuilder {
+    client
+        .post("https://api.pushover.net/1/messages.json")
+        .json(&json!({
+            "token": env.pushover_token,
+            "user": env.pushover_user,
+            "title": format!("Swaparr ─ {}", instance.platform),
+            "message": format!("{} removed by Swaparr", download.name),
+            "priority": env.pushover_priority,
+            // Required by emergency priority, ignored by the others: retry every minute for an hour.
+            "retry": 60,
+            "expire": 3600,
+        }))
+}
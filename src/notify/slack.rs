@@ -0,0 +1,41 @@
+use reqwest::blocking::{Client, RequestBuilder};
+use serde_json::{json, Value};
+
+use crate::{queue, utils};
+
+// Slack allows up to 50 blocks per message, one is used by the header.
+pub const MAX_DOWNLOADS: usize = 49;
+
+// Builds a Slack incoming webhook request with a section for every removed download.
+pub fn removed(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    client: &Client,
+    removals: &[(&queue::Download, u32)],
+) -> RequestBuilder {
+    let mut blocks: Vec<Value> = vec![json!({
+        "type": "header",
+        "text": {
+            "type": "plain_text",
+            "text": format!("Swaparr removed {} download(s) from {}", removals.len(), instance.id),
+        },
+    })];
+
+    for (download, strikes) in removals {
+        blocks.push(json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": format!("*{}*", download.name) },
+            "fields": [
+                { "type": "mrkdwn", "text": format!("*Size*\n{}", utils::parse::bytes_to_size_string(&download.size, &env.size_units)) },
+                { "type": "mrkdwn", "text": format!("*ETA*\n{}", utils::parse::ms_to_eta_string(&download.eta)) },
+                { "type": "mrkdwn", "text": format!("*Strikes*\n{}/{}", strikes, env.max_strikes) },
+            ],
+        }));
+    }
+
+    client.post(&env.slack_webhook).json(&json!({
+        // Shown in notifications, where blocks are not rendered.
+        "text": format!("Swaparr removed {} download(s) from {}", removals.len(), instance.id),
+        "blocks": blocks,
+    }))
+}
@@ -0,0 +1,30 @@
+use reqwest::blocking::{Client, RequestBuilder};
+use serde_json::json;
+
+use crate::{queue, utils};
+
+// Builds a Telegram Bot API request with a message describing the removed download.
+pub fn removed(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    client: &Client,
+    download: &queue::Download,
+    strikes: u32,
+) -> RequestBuilder {
+    client
+        .post(format!(
+            "https://api.telegram.org/bot{}/sendMessage",
+            env.telegram_bot_token
+        ))
+        .json(&json!({
+            "chat_id": env.telegram_chat_id,
+            "text": format!(
+                "Download removed by Swaparr ({})\n\n{}\nSize: {}\nStrikes: {}/{}",
+                instance.platform,
+                download.name,
+                utils::parse::bytes_to_size_string(&download.size, &env.size_units),
+                strikes,
+                env.max_strikes,
+            ),
+        }))
+}
@@ -0,0 +1,24 @@
+use reqwest::blocking::{Client, RequestBuilder};
+use serde_json::json;
+
+use crate::{queue, utils};
+
+// Builds a generic webhook request with a JSON payload describing the event.
+pub fn event(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    client: &Client,
+    event: &str,
+    download: &queue::Download,
+    strikes: u32,
+) -> RequestBuilder {
+    client.post(&env.webhook_url).json(&json!({
+        "event": event,
+        "torrent_name": download.name,
+        "size_bytes": download.size,
+        "eta_ms": download.eta,
+        "strikes": strikes,
+        "platform": instance.platform,
+        "instance": instance.id,
+    }))
+}
@@ -0,0 +1,103 @@
+use reqwest::blocking::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::utils;
+
+#[derive(Deserialize, Debug)]
+struct Indexer {
+    id: u32,
+    name: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug)]
+struct IndexerStatus {
+    indexerId: u32,
+    disabledTill: Option<String>,
+}
+
+// Requests an endpoint of the Prowlarr API, authorized by its API key.
+fn get<T: DeserializeOwned>(
+    env: &utils::system::Envs,
+    client: &Client,
+    endpoint: &str,
+) -> Result<T, String> {
+    let url = env.prowlarr_url.trim_end_matches('/');
+
+    client
+        .get(format!("{url}/api/v1/{endpoint}"))
+        .header("X-Api-Key", &env.prowlarr_apikey)
+        .send()
+        .and_then(|res| res.error_for_status())
+        .and_then(|res| res.json::<T>())
+        .map_err(|error| error.to_string())
+}
+
+// Obtains the lowercase names of the indexers Prowlarr has disabled for now, empty when it could not
+// be reached.
+pub fn unavailable(env: &utils::system::Envs, client: &Client) -> Vec<String> {
+    let indexers = get::<Vec<IndexerStatus>>(env, client, "indexerstatus").and_then(|statuses| {
+        // Prowlarr keeps the status of an indexer around after it recovered, "disabledTill" tells.
+        let now = chrono::Utc::now();
+        let disabled: Vec<u32> = statuses
+            .into_iter()
+            .filter(|status| {
+                status
+                    .disabledTill
+                    .as_ref()
+                    .and_then(|till| chrono::DateTime::parse_from_rfc3339(till).ok())
+                    .is_some_and(|till| till.to_utc() > now)
+            })
+            .map(|status| status.indexerId)
+            .collect();
+
+        if disabled.is_empty() {
+            return Ok(vec![]);
+        }
+
+        get::<Vec<Indexer>>(env, client, "indexer").map(|indexers| {
+            indexers
+                .into_iter()
+                .filter(|indexer| disabled.contains(&indexer.id))
+                .map(|indexer| indexer.name.to_ascii_lowercase())
+                .collect()
+        })
+    });
+
+    match indexers {
+        Ok(indexers) => indexers,
+        Err(error) => {
+            utils::log::alert(
+                "WARN",
+                "Unable to obtain the indexer status from Prowlarr.",
+                "Downloads are removed regardless of their indexer for this run.",
+                Some(error),
+            );
+            vec![]
+        }
+    }
+}
+
+// Whether the indexer of a download is disabled, the starr instances add " (Prowlarr)" to the names
+// of the indexers synced from Prowlarr.
+pub fn is_down(unavailable: &[String], indexer: &str) -> bool {
+    let indexer = indexer.to_ascii_lowercase();
+    let indexer = indexer.strip_suffix(" (prowlarr)").unwrap_or(&indexer);
+
+    unavailable.iter().any(|name| name == indexer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synced_indexers_match_their_prowlarr_name() {
+        let unavailable = vec![String::from("nyaa"), String::from("1337x")];
+
+        assert!(is_down(&unavailable, "Nyaa (Prowlarr)"));
+        assert!(is_down(&unavailable, "1337x"));
+        assert!(!is_down(&unavailable, "Torznab (Prowlarr)"));
+        assert!(!is_down(&unavailable, ""));
+    }
+}
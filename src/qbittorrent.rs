@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use reqwest::{
+    blocking::Client,
+    header::{COOKIE, SET_COOKIE},
+};
+use serde::Deserialize;
+
+use crate::utils;
+
+// qBittorrent reports this ETA (100 days, in seconds) when it is unknown.
+const INFINITE_ETA: u64 = 8640000;
+
+#[derive(Deserialize, Debug)]
+pub struct Torrent {
+    pub hash: String,
+    pub state: String,
+    pub eta: u64,
+    // Seeders connected to, and in the whole swarm as reported by the trackers; "-1" until they did.
+    #[serde(default)]
+    pub num_seeds: u64,
+    #[serde(default = "unreported")]
+    pub num_complete: i64,
+}
+
+fn unreported() -> i64 {
+    -1
+}
+
+impl Torrent {
+    // Status as used by the strike rules, when qBittorrent knows better than the queue.
+    pub fn status(&self) -> Option<&str> {
+        match self.state.as_str() {
+            "metaDL" | "forcedMetaDL" => Some("metadata"),
+            "stalledDL" => Some("stalled"),
+            "queuedDL" => Some("queued"),
+            _ => None,
+        }
+    }
+
+    // Seeders of the torrent, unknown until a tracker reported the swarm.
+    pub fn seeders(&self) -> Option<u64> {
+        u64::try_from(self.num_complete)
+            .ok()
+            .map(|swarm| swarm.max(self.num_seeds))
+    }
+
+    // ETA in milliseconds, "0" when unknown.
+    pub fn eta(&self) -> u64 {
+        if self.eta >= INFINITE_ETA {
+            0
+        } else {
+            self.eta * 1000
+        }
+    }
+}
+
+// Logs in to qBittorrent, returns the session cookie; none when no username is set.
+fn login(env: &utils::system::Envs, client: &Client) -> Result<Option<String>, String> {
+    if env.qbittorrent_user.is_empty() {
+        return Ok(None);
+    }
+
+    let url = env.qbittorrent_url.trim_end_matches('/');
+
+    let res = client
+        .post(format!("{url}/api/v2/auth/login"))
+        .header("Referer", url)
+        .form(&[
+            ("username", &env.qbittorrent_user),
+            ("password", &env.qbittorrent_pass),
+        ])
+        .send()
+        .map_err(|error| error.to_string())?;
+
+    // Invalid credentials are answered with "Fails." and no cookie, rather than an error status.
+    res.headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|cookie| cookie.to_str().ok())
+        .filter_map(|cookie| cookie.split(';').next())
+        .find(|cookie| cookie.starts_with("SID="))
+        .map(|cookie| Some(cookie.to_string()))
+        .ok_or_else(|| String::from("qBittorrent has refused the username or password."))
+}
+
+// Obtains the torrents of qBittorrent by their lowercase hash, empty when it could not be reached.
+pub fn torrents(env: &utils::system::Envs, client: &Client) -> HashMap<String, Torrent> {
+    let url = env.qbittorrent_url.trim_end_matches('/');
+
+    let torrents = login(env, client).and_then(|cookie| {
+        let mut request = client.get(format!("{url}/api/v2/torrents/info"));
+
+        if let Some(cookie) = cookie {
+            request = request.header(COOKIE, cookie);
+        }
+
+        request
+            .send()
+            .and_then(|res| res.error_for_status())
+            .and_then(|res| res.json::<Vec<Torrent>>())
+            .map_err(|error| error.to_string())
+    });
+
+    match torrents {
+        Ok(torrents) => torrents
+            .into_iter()
+            .map(|torrent| (torrent.hash.to_ascii_lowercase(), torrent))
+            .collect(),
+        Err(error) => {
+            utils::log::alert(
+                "WARN",
+                "Unable to obtain torrents from qBittorrent.",
+                "Falls back onto the queue of the starr instance for this run.",
+                Some(error),
+            );
+            HashMap::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn torrent(json: &str) -> Torrent {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn seeders_are_unknown_until_a_tracker_reports_them() {
+        assert_eq!(
+            torrent(r#"{"hash": "A", "state": "stalledDL", "eta": 8640000}"#).seeders(),
+            None
+        );
+        assert_eq!(
+            torrent(r#"{"hash": "A", "state": "stalledDL", "eta": 8640000, "num_seeds": 0, "num_complete": -1}"#).seeders(),
+            None
+        );
+        assert_eq!(
+            torrent(r#"{"hash": "A", "state": "stalledDL", "eta": 8640000, "num_seeds": 0, "num_complete": 0}"#).seeders(),
+            Some(0)
+        );
+        assert_eq!(
+            torrent(r#"{"hash": "A", "state": "downloading", "eta": 60, "num_seeds": 3, "num_complete": 12}"#).seeders(),
+            Some(12)
+        );
+    }
+}
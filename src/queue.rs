@@ -1,13 +1,44 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+    thread::{self, sleep},
+    time::{Duration, Instant},
+};
 
 use reqwest::blocking as request;
 use serde::Deserialize;
 
-use crate::{libs, utils};
+use crate::{audit, health, libs, metrics, notify, prowlarr, qbittorrent, utils};
+
+// Most strikes a single run gives a download with "ESCALATING_STRIKES".
+const MAX_ESCALATED_STRIKES: u32 = 3;
+
+// Strike reason of a download whose ETA exceeds the "MAX_DOWNLOAD_TIME", the only one that escalates.
+const ETA_TOO_LONG: &str = "ETA too long";
+
+// Widest reason shown in the table, a matched error message can be any length.
+const REASON_WIDTH: usize = 48;
+
+// Downloads removed within the "REMOVAL_COOLDOWN", by instance id and media id, to notice a re-grab.
+static REMOVED: Mutex<Vec<(String, u32, Instant)>> = Mutex::new(Vec::new());
 
 #[derive(Deserialize)]
 struct Response {
     records: Vec<Record>,
+    #[serde(rename = "totalRecords")]
+    total_records: Option<usize>,
+}
+
+// The details endpoint answers with a bare list of every record, the basic one with a page.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Page {
+    Paginated(Response),
+    Details(Vec<Record>),
 }
 
 #[allow(non_snake_case)]
@@ -15,12 +46,29 @@ struct Response {
 pub struct Record {
     id: u32,
     size: f64,
+    sizeleft: Option<f64>,
     timeleft: Option<String>,
     status: String,
+    trackedDownloadStatus: Option<String>,
+    trackedDownloadState: Option<String>,
     errorMessage: Option<String>,
+    statusMessages: Option<Vec<StatusMessage>>,
+    protocol: Option<String>,
+    downloadClient: Option<String>,
+    indexer: Option<String>,
+    added: Option<String>,
+    movieId: Option<u32>,
+    episodeId: Option<u32>,
+    albumId: Option<u32>,
+    bookId: Option<u32>,
+    pub downloadId: Option<String>,
+    pub title: Option<String>,
     pub movie: Option<NestedRecord>,
     pub series: Option<NestedRecord>,
+    pub episode: Option<NestedRecord>,
     pub album: Option<NestedRecord>,
+    pub artist: Option<ArtistRecord>,
+    pub author: Option<AuthorRecord>,
     pub book: Option<NestedRecord>,
 }
 
@@ -29,171 +77,1727 @@ pub struct NestedRecord {
     pub title: String,
 }
 
+// Warnings of the starr instance about a download, e.g. "One or more episodes expected".
+#[derive(Deserialize, Debug)]
+pub struct StatusMessage {
+    pub title: Option<String>,
+    #[serde(default)]
+    pub messages: Vec<String>,
+}
+
+// Lidarr artists carry their name in "artistName" rather than "title".
+#[derive(Deserialize, Debug)]
+pub struct ArtistRecord {
+    #[serde(rename = "artistName")]
+    pub name: String,
+}
+
+// Readarr authors carry their name in "authorName" rather than "title".
+#[derive(Deserialize, Debug)]
+pub struct AuthorRecord {
+    #[serde(rename = "authorName")]
+    pub name: String,
+}
+
+// Keeps track of a download in between runs.
+#[derive(Debug, Default, Clone)]
+pub struct Tracker {
+    pub strikes: u32,
+    pub eta: Option<u64>,
+    pub etas: VecDeque<u64>,
+    pub first_seen: Option<Instant>,
+    pub last_strike: Option<Instant>,
+    pub notified: bool,
+    // Runs in a row qBittorrent reported the download without any seeders.
+    pub zero_seeders: u32,
+}
+
+// Shared by every instance within a single run.
+pub struct Cycle {
+    // The first "WARMUP_CYCLES" runs never remove a download.
+    pub warmup: bool,
+    // Indexers Prowlarr has disabled for now, a removed download could not be replaced from those.
+    pub indexers_down: Vec<String>,
+    // Removals left for this run, "MAX_REMOVALS_PER_CYCLE" is shared by every instance.
+    removals_left: AtomicU32,
+}
+
+impl Cycle {
+    pub fn new(env: &utils::system::Envs, clients: &libs::http::Clients, warmup: bool) -> Cycle {
+        Cycle {
+            warmup,
+            indexers_down: if env.prowlarr_url.is_empty() {
+                vec![]
+            } else {
+                prowlarr::unavailable(env, &clients.services)
+            },
+            removals_left: AtomicU32::new(match env.max_removals_per_cycle {
+                0 => u32::MAX,
+                max_removals => max_removals,
+            }),
+        }
+    }
+
+    // Takes a removal out of the budget of this run, false once none are left.
+    fn schedule(&self) -> bool {
+        self.removals_left
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok()
+    }
+}
+
+// Outcome of processing the queue of an instance, a single run.
+#[derive(Default)]
+pub struct Summary {
+    pub total: usize,
+    pub striked: usize,
+    pub removed: usize,
+    pub ignored: usize,
+    pub queued: usize,
+    // The queue could not be obtained, nothing was processed and the strikes are left as they are.
+    pub failed: bool,
+    // Size of the downloads removed this run, in bytes.
+    pub reclaimed: u64,
+    pub table_contents: Vec<libs::table::TableContent>,
+    // Rows of the table of the downloads removed this run, a lingering download is only listed once.
+    pub removed_rows: Vec<usize>,
+    // Id, reason and size of the downloads that would have been removed in dry-run mode.
+    pub would_remove: Vec<(u32, String, u64)>,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Download {
     pub id: u32,
+    // Every queue record of the download, more than one when "GROUP_BY_DOWNLOAD" merged them.
+    pub ids: Vec<u32>,
+    pub download_id: String,
+    // The movies, episodes, albums or books the download is for, by their id in the starr instance.
+    pub media_ids: Vec<u32>,
     pub name: String,
     pub size: u64,
+    pub sizeleft: u64,
     pub status: String,
+    pub tracked_status: String,
+    pub tracked_state: String,
+    pub protocol: String,
+    pub download_client: String,
+    pub indexer: String,
+    pub messages: Vec<String>,
     pub eta: u64,
+    pub age: u64,
+    // Only known with "QBITTORRENT_URL", the queue does not report seeders.
+    pub seeders: Option<u64>,
 }
 
-// Delete Download from Starr.
-pub fn delete(url: &String) {
-    match request::Client::new().delete(url).send() {
-        Ok(_) => (),
-        Err(error) => {
+// Delete Download from Starr, retries with an exponential backoff before giving up.
+pub fn delete(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    client: &request::Client,
+    ids: &[u32],
+) -> bool {
+    let url = utils::parse::deleteapi(env, instance, ids);
+    let mut attempt: u32 = 0;
+
+    loop {
+        // A group of records is removed at once, by the bulk endpoint.
+        let request = match ids {
+            [_] => client.delete(&url),
+            _ => client.delete(&url).json(&serde_json::json!({ "ids": ids })),
+        };
+
+        let error = match libs::http::send(libs::http::authorize(env, instance, request)) {
+            Ok(res) if res.status().is_success() => return true,
+            Ok(res) => format!("The API has responded with status \"{}\".", res.status()),
+            Err(error) => error.to_string(),
+        };
+
+        if attempt >= env.delete_retries {
+            metrics::increment("swaparr_api_errors_total", instance);
             utils::log::alert(
                 "WARN",
                 "Failed to remove download, will attempt again next run.",
                 "The API has refused this request.",
-                Some(error.to_string()),
+                Some(error),
             );
+            return false;
+        }
+
+        // Waits 1x, 2x, 4x, etc.. the configured backoff between attempts.
+        sleep(Duration::from_millis(
+            env.delete_backoff_ms.saturating_mul(1 << attempt.min(16)),
+        ));
+        attempt += 1;
+    }
+}
+
+// Why the queue could not be obtained, the caller decides what to do about it.
+#[derive(Debug)]
+pub enum QueueError {
+    // The API could not be reached, or did not respond in time.
+    Connection(reqwest::Error),
+    // The API responded, but not with a queue.
+    Response(reqwest::Error),
+}
+
+impl QueueError {
+    // A human-readable explanation, for the logs.
+    pub fn reason(&self) -> &str {
+        match self {
+            QueueError::Connection(error) | QueueError::Response(error) if error.is_timeout() => {
+                "The API did not respond within the set \"REQUEST_TIMEOUT\"."
+            }
+            QueueError::Connection(_) => "The connection to the API was unsuccessful.",
+            QueueError::Response(_) => "The API has responded with an invalid response.",
         }
     }
 }
 
-// Obtains Downloads from Starr.
-pub fn get(platform: &str, url: &str) -> Vec<Download> {
-    let res: Response = match request::get(url) {
-        Ok(res) => match res.json() {
-            Ok(res) => res,
+impl std::fmt::Display for QueueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            QueueError::Connection(error) | QueueError::Response(error) => error.fmt(f),
+        }
+    }
+}
+
+// Obtains Downloads from Starr, an error rather than an empty queue when the API fails.
+pub fn get(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    clients: &libs::http::Clients,
+) -> Result<Vec<Download>, QueueError> {
+    let details = env.use_queue_details == "true";
+    let queueapi = utils::parse::queueapi(&instance.platform, &instance.baseapi, details);
+
+    let mut records: Vec<Record> = vec![];
+    let mut page: usize = 1;
+
+    // The queue is paginated, keep requesting pages until every record is obtained.
+    loop {
+        let url = if details {
+            queueapi.clone()
+        } else {
+            format!("{queueapi}&page={page}")
+        };
+
+        let res = libs::http::send(libs::http::authorize(env, instance, clients.starr.get(url)))
+            .map_err(QueueError::Connection)
+            .and_then(|res| res.json::<Page>().map_err(QueueError::Response));
+
+        let res: Response = match res {
+            Ok(Page::Paginated(res)) => res,
+            Ok(Page::Details(records)) => Response {
+                total_records: Some(records.len()),
+                records,
+            },
             Err(error) => {
-                utils::log::alert(
-                    "WARN",
-                    "Unable to process queue, will attempt again next run.",
-                    "The API has responded with an invalid response.",
-                    Some(error.to_string()),
-                );
-                Response { records: vec![] }
+                metrics::increment("swaparr_api_errors_total", instance);
+                health::report(instance, false);
+                return Err(error);
             }
-        },
-        Err(error) => {
-            utils::log::alert(
-                "WARN",
-                "Unable to process queue, will attempt again next run.",
-                "The connection to the API was unsuccessful.",
-                Some(error.to_string()),
-            );
-            Response { records: vec![] }
+        };
+
+        let last_page = res.records.is_empty()
+            || records.len() + res.records.len() >= res.total_records.unwrap_or(0);
+
+        records.extend(res.records);
+
+        if last_page {
+            break;
         }
+        page += 1;
+    }
+
+    health::report(instance, true);
+    metrics::set("swaparr_queue_size", instance, records.len() as u64);
+
+    // qBittorrent knows the true state of its torrents, cross-referenced by their hash.
+    let torrents = if env.qbittorrent_url.is_empty() {
+        HashMap::new()
+    } else {
+        qbittorrent::torrents(env, &clients.services)
     };
 
     let mut downloads: Vec<Download> = vec![];
 
-    res.records.iter().for_each(|record| {
-        let eta = {
+    records.iter().for_each(|record| {
+        let mut eta = {
             let timeleft = record.timeleft.clone().unwrap_or_else(|| "0".to_string());
             utils::parse::string_hms_to_ms(&timeleft)
         };
+        let mut seeders = None;
 
         // Determine status of download.
         // - Please inform me; if you have a different method
         //   on how to identify a download that is fetching metadata.
-        let status = if let Some(error_message) = &record.errorMessage {
+        let mut status = if record
+            .status
+            .eq_ignore_ascii_case("downloadClientUnavailable")
+        {
+            // The download client is down, the download itself is not to blame.
+            "unavailable".to_string()
+        } else if let Some(error_message) = &record.errorMessage {
             if error_message.to_ascii_lowercase().contains("metadata") {
                 "metadata".to_string()
+            } else if error_message.to_ascii_lowercase().contains("stalled") {
+                "stalled".to_string()
             } else {
-                record.status.clone()
+                record.status.to_ascii_lowercase()
             }
         } else {
-            record.status.clone()
+            record.status.to_ascii_lowercase()
         };
 
+        if let Some(torrent) = record
+            .downloadId
+            .as_ref()
+            .and_then(|hash| torrents.get(&hash.to_ascii_lowercase()))
+        {
+            if let Some(torrent_status) = torrent.status() {
+                status = torrent_status.to_string();
+            }
+            eta = torrent.eta();
+            seeders = torrent.seeders();
+        }
+
+        // The status of the download as seen by the starr instance, e.g. "ok" or "warning".
+        let tracked_status = record
+            .trackedDownloadStatus
+            .clone()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
         downloads.push(Download {
             id: record.id,
-            name: utils::parse::recordname(&platform, &record),
+            ids: vec![record.id],
+            download_id: record
+                .downloadId
+                .clone()
+                .unwrap_or_default()
+                .to_ascii_lowercase(),
+            // Sonarr names a download after its series, the episode tells its siblings apart.
+            media_ids: record
+                .episodeId
+                .or(record.movieId)
+                .or(record.albumId)
+                .or(record.bookId)
+                .into_iter()
+                .collect(),
+            name: utils::parse::recordname(&instance.platform, record),
             size: record.size as u64,
+            sizeleft: record.sizeleft.unwrap_or(record.size) as u64,
             status,
+            tracked_status,
+            // The stage of the download as seen by the starr instance, e.g. "importpending".
+            tracked_state: record
+                .trackedDownloadState
+                .clone()
+                .unwrap_or_default()
+                .to_ascii_lowercase(),
+            // Either "torrent" or "usenet", unknown when the client did not report it.
+            protocol: record
+                .protocol
+                .clone()
+                .unwrap_or_default()
+                .to_ascii_lowercase(),
+            download_client: record
+                .downloadClient
+                .clone()
+                .unwrap_or_default()
+                .to_ascii_lowercase(),
+            // Shown as reported, e.g. "Nyaa", but compared case-insensitively.
+            indexer: record.indexer.clone().unwrap_or_default(),
+            // Every message the starr instance attached, matched against "ERROR_PATTERNS".
+            messages: record
+                .errorMessage
+                .iter()
+                .cloned()
+                .chain(record.statusMessages.iter().flatten().flat_map(|status| {
+                    status
+                        .title
+                        .iter()
+                        .cloned()
+                        .chain(status.messages.iter().cloned())
+                }))
+                .collect(),
             eta,
+            // Milliseconds since the download was grabbed, "0" when the starr instance did not report it.
+            age: record
+                .added
+                .as_ref()
+                .and_then(|added| chrono::DateTime::parse_from_rfc3339(added).ok())
+                .and_then(|added| (chrono::Utc::now() - added.to_utc()).to_std().ok())
+                .map_or(0, |age| age.as_millis() as u64),
+            seeders,
         });
     });
 
-    downloads
+    if env.group_by_download == "true" {
+        downloads = group(downloads);
+    }
+
+    Ok(downloads)
 }
 
-// Determines if the download is eligible to be striked.
+// Merges the queue records of the same download, e.g. every episode of a season pack, into one.
+// The group is striked and removed as a whole, its strikes are tracked by its lowest record id.
+fn group(downloads: Vec<Download>) -> Vec<Download> {
+    let mut grouped: Vec<Download> = vec![];
+
+    for download in downloads {
+        match grouped.iter_mut().find(|group| {
+            !download.download_id.is_empty() && group.download_id == download.download_id
+        }) {
+            Some(group) => {
+                group.id = group.id.min(download.id);
+                group.ids.push(download.id);
+                group.media_ids.extend(download.media_ids);
+                // The records share the download, so the largest of them covers it best.
+                group.size = group.size.max(download.size);
+                group.sizeleft = group.sizeleft.max(download.sizeleft);
+                group.eta = group.eta.max(download.eta);
+                group.messages.extend(download.messages);
+            }
+            None => grouped.push(download),
+        }
+    }
+
+    for group in &mut grouped {
+        group.ids.sort_unstable();
+    }
+
+    grouped
+}
+
+// Obtains and processes the queue of an instance, returns a summary of the run.
+pub fn run(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    clients: &libs::http::Clients,
+    strikelist: &mut HashMap<u32, Tracker>,
+    cycle: &Cycle,
+) -> Summary {
+    match get(env, instance, clients) {
+        Ok(queue_items) => {
+            prune(strikelist, &queue_items);
+            process(env, instance, clients, queue_items, strikelist, cycle)
+        }
+        // An unreachable API is no empty queue, the strikes are kept as they are.
+        Err(error) => {
+            utils::log::alert(
+                "WARN",
+                "Unable to process queue, will attempt again next run.",
+                error.reason(),
+                Some(error.to_string()),
+            );
+            Summary {
+                failed: true,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+// Forgets the downloads that left the queue, a reused id never inherits stale strikes.
+// Only called with a queue that was obtained, a failing API would otherwise wipe every strike.
+pub fn prune(strikelist: &mut HashMap<u32, Tracker>, queue_items: &[Download]) {
+    strikelist.retain(|&id, _| queue_items.iter().any(|download| download.id == id));
+}
+
+// Download ids listed in the "PARDON_FILE" for this instance, either as "12" or "radarr:12".
+fn pardons(env: &utils::system::Envs, instance: &utils::system::Instance) -> Vec<u32> {
+    if env.pardon_file.is_empty() {
+        return vec![];
+    }
+
+    // Re-read every run, so a pardon takes effect without a restart.
+    let contents = match fs::read_to_string(&env.pardon_file) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return vec![],
+        Err(error) => {
+            utils::log::alert(
+                "WARN",
+                "Failed to read the \"PARDON_FILE\".",
+                "No downloads are pardoned this run.",
+                Some(error.to_string()),
+            );
+            return vec![];
+        }
+    };
+
+    contents
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|entry| match entry.split_once(':') {
+            Some((id, download)) if id.eq_ignore_ascii_case(&instance.id) => download.parse().ok(),
+            Some(_) => None,
+            None => entry.parse().ok(),
+        })
+        .collect()
+}
+
+// What the bypass rules decided for a download, before any strike is given.
+#[derive(Debug, PartialEq)]
+enum Bypass {
+    // Pardoned downloads start over, and are left alone for as long as they are listed.
+    Pardoned,
+    // Blacklisted downloads skip straight to removal.
+    Blacklisted,
+    // Left alone this run, along with the state and the rule that decided it.
+    Skipped(&'static str, String),
+    // None of the rules matched, the strike rules decide.
+    Eligible,
+}
+
+// Percentage of the download that has been completed.
+fn progress(download: &Download) -> f64 {
+    if download.size > 0 {
+        download.size.saturating_sub(download.sizeleft) as f64 / download.size as f64 * 100.0
+    } else {
+        0.0
+    }
+}
+
+// Rules are evaluated in order of precedence, the first one that matches decides:
+// pardon → protocol → client → indexer → size-ignore → whitelist → client unavailable
+// → tracked state → blacklist → queued → grace → progress → strike.
+fn bypass(
+    env: &utils::system::Envs,
+    download: &Download,
+    pardoned: bool,
+    ignore_above: u64,
+    ignore_below: u64,
+    seen: Duration,
+) -> Bypass {
+    let skipped = |state: &'static str, reason: &str| Bypass::Skipped(state, String::from(reason));
+
+    if pardoned {
+        Bypass::Pardoned
+    } else if env.protocol != "both" && download.protocol != env.protocol {
+        skipped("Ignored", "Protocol")
+    } else if (!env.download_client_include.is_empty()
+        && !env
+            .download_client_include
+            .contains(&download.download_client))
+        || env
+            .download_client_exclude
+            .contains(&download.download_client)
+    {
+        skipped("Ignored", "Download client")
+    } else if env
+        .indexer_exclude
+        .contains(&download.indexer.to_ascii_lowercase())
+    {
+        Bypass::Skipped("Ignored", format!("Indexer: {}", download.indexer))
+    } else if download.size >= ignore_above {
+        // Inclusive, a download of exactly "IGNORE_ABOVE_SIZE" is ignored as well.
+        skipped("Ignored", "Above size")
+    } else if download.size < ignore_below {
+        // Exclusive, a download of exactly "IGNORE_BELOW_SIZE" is monitored. Unset or "0 B" never
+        // matches, as no download can be smaller than that.
+        skipped("Ignored", "Below size")
+    } else if env
+        .whitelist
+        .iter()
+        .any(|pattern| pattern.is_match(&download.name))
+    {
+        skipped("Ignored", "Whitelisted")
+    } else if download.status == "unavailable" {
+        // Every download stalls while the download client is unreachable, none of them is striked
+        // until it is back.
+        skipped("Paused", "Client unavailable")
+    } else if !download.tracked_state.is_empty() && download.tracked_state != "downloading" {
+        // Completed downloads being imported report odd ETAs, those are left alone.
+        if download.tracked_state.starts_with("import") {
+            skipped("Importing", "Post-processing")
+        } else {
+            skipped("Ignored", "Post-processing")
+        }
+    } else if env
+        .blacklist
+        .iter()
+        .any(|pattern| pattern.is_match(&download.name))
+    {
+        Bypass::Blacklisted
+    } else if download.status == "queued" {
+        skipped("Queued", "")
+    } else if seen.as_millis()
+        < utils::parse::string_time_notation_to_ms(&env.grace_period).unwrap() as u128
+    {
+        // Freshly grabbed downloads get some time to find peers before being judged.
+        skipped("Normal", "Grace period")
+    } else if env.min_progress > 0 && progress(download) >= env.min_progress as f64 {
+        // Nearly completed downloads are never striked, even if the ETA spikes.
+        skipped("Normal", "Above progress")
+    } else {
+        Bypass::Eligible
+    }
+}
+
+// When the download is for something removed within the "REMOVAL_COOLDOWN", by the same instance.
+fn regrabbed(instance: &utils::system::Instance, download: &Download) -> Option<Instant> {
+    REMOVED
+        .lock()
+        .unwrap_or_else(|error| error.into_inner())
+        .iter()
+        .find(|(removed_instance, media_id, _)| {
+            removed_instance == &instance.id && download.media_ids.contains(media_id)
+        })
+        .map(|(_, _, removed)| *removed)
+}
+
+// Determines if the download is eligible to be striked, returns a summary with the contents of the table.
 pub fn process(
     env: &utils::system::Envs,
-    baseapi: &String,
+    instance: &utils::system::Instance,
+    clients: &libs::http::Clients,
     queue_items: Vec<Download>,
-    strikelist: &mut HashMap<u32, u32>,
-) {
+    strikelist: &mut HashMap<u32, Tracker>,
+    cycle: &Cycle,
+) -> Summary {
     let mut table_contents: Vec<libs::table::TableContent> = vec![];
 
+    // Downloads to remove, along with their row in the table and their strikes.
+    let mut removals: Vec<(usize, Download, u32)> = vec![];
+
+    // Downloads that would have been removed this run, in dry-run mode.
+    let mut would_remove: Vec<(u32, String, u64)> = vec![];
+
+    // Thresholds scoped to the platform take precedence over the global ones, the
+    // "MAX_DOWNLOAD_TIME_BUCKETS" included.
+    let scoped_max_download_time_ms =
+        instance
+            .max_download_time
+            .as_ref()
+            .map(|max_download_time| {
+                utils::parse::string_time_notation_to_ms(max_download_time).unwrap() as u64
+            });
+    let ignore_above_size = instance
+        .ignore_above_size
+        .as_ref()
+        .unwrap_or(&env.ignore_above_size);
+    let ignore_below_size = instance
+        .ignore_below_size
+        .as_ref()
+        .unwrap_or(&env.ignore_below_size);
+    let ignore_above = utils::parse::string_bytesize_to_bytes(ignore_above_size, &env.size_units)
+        .unwrap()
+        .as_u64();
+    let ignore_below = utils::parse::string_bytesize_to_bytes(ignore_below_size, &env.size_units)
+        .unwrap()
+        .as_u64();
+
+    let pardoned = pardons(env, instance);
+
+    // Removals older than the "REMOVAL_COOLDOWN" are no longer of interest.
+    let removal_cooldown = Duration::from_millis(
+        utils::parse::string_time_notation_to_ms(&env.removal_cooldown).unwrap() as u64,
+    );
+    REMOVED
+        .lock()
+        .unwrap_or_else(|error| error.into_inner())
+        .retain(|(_, _, removed)| removed.elapsed() < removal_cooldown);
+
     for download in queue_items {
-        let id = download.id.clone();
+        let id = download.id;
         let mut state = String::from("Normal");
 
+        // A new download of something removed within the "REMOVAL_COOLDOWN" is likely to stall as well,
+        // the starr instance grabbed another release right away.
+        if !strikelist.contains_key(&id) {
+            if let Some(removed) = regrabbed(instance, &download) {
+                utils::log::alert(
+                    "WARN",
+                    "Download was re-grabbed within the \"REMOVAL_COOLDOWN\".",
+                    &download.name,
+                    Some(format!(
+                        "It was removed {} ago.",
+                        humantime::format_duration(Duration::from_secs(
+                            removed.elapsed().as_secs()
+                        ))
+                    )),
+                );
+                utils::log::event(instance, "regrab", &download, 0, "Re-grabbed");
+            }
+        }
+
         // Add non-existing download to strikelist.
-        let mut strikes: u32 = match strikelist.get(&id) {
-            Some(strikes) => strikes.clone(),
-            None => {
-                strikelist.insert(id, 0);
-                0
+        let tracker = strikelist.entry(id).or_default();
+        let mut strikes: u32 = tracker.strikes;
+        let first_seen = *tracker.first_seen.get_or_insert_with(Instant::now);
+
+        // Strikes act on the median of the latest "ETA_WINDOW" ETAs, a single spike is ignored.
+        while tracker.etas.len() >= env.eta_window {
+            tracker.etas.pop_front();
+        }
+        tracker.etas.push_back(download.eta);
+
+        let eta = {
+            let mut etas: Vec<u64> = tracker.etas.iter().copied().collect();
+            etas.sort_unstable();
+            let middle = etas.len() / 2;
+            if etas.len().is_multiple_of(2) {
+                (etas[middle - 1] + etas[middle]) / 2
+            } else {
+                etas[middle]
             }
         };
 
+        // A tracker may be down for a run, a torrent is only dead once it stays without seeders.
+        if download.seeders == Some(0) {
+            tracker.zero_seeders += 1;
+        } else {
+            tracker.zero_seeders = 0;
+        }
+
         // -- Bypass Section: Rules that define if a download is eligible to be striked.
 
-        let mut bypass: bool = false;
+        let progress = progress(&download);
 
-        if download.size
-            >= utils::parse::string_bytesize_to_bytes(&env.ignore_above_size)
-                .unwrap()
-                .as_u64()
-        {
-            state = String::from("Ignored");
-            bypass = true;
-        }
+        // Which rule decided the state of the download, shown in the table.
+        let mut reason = String::new();
+
+        let verdict = bypass(
+            env,
+            &download,
+            pardoned.contains(&id),
+            ignore_above,
+            ignore_below,
+            first_seen.elapsed(),
+        );
+        let blacklisted = verdict == Bypass::Blacklisted;
+
+        let bypass: bool = match verdict {
+            Bypass::Pardoned => {
+                strikes = 0;
+                tracker.last_strike = None;
+                state = String::from("Ignored");
+                reason = String::from("Pardoned");
+                true
+            }
+            Bypass::Blacklisted => {
+                utils::log::alert(
+                    "INFO",
+                    "Download matches the \"BLACKLIST\", removing it immediately.",
+                    &download.name,
+                    None,
+                );
+                strikes = env.max_strikes;
+                reason = String::from("Blacklisted");
+                false
+            }
+            Bypass::Skipped(skipped_state, skipped_reason) => {
+                state = String::from(skipped_state);
+                reason = skipped_reason;
+                true
+            }
+            Bypass::Eligible => false,
+        };
 
-        if download.status == "queued" {
-            state = String::from("Queued");
-            bypass = true;
+        if state == "Ignored" {
+            utils::log::event(instance, "ignore", &download, strikes, &reason);
         }
 
         // -- Strike Section: Rules that define when to strike a download.
 
         if !bypass {
-            let max_download_time_ms =
-                utils::parse::string_time_notation_to_ms(&env.max_download_time).unwrap() as u64;
+            // The first of the "MAX_DOWNLOAD_TIME_BUCKETS" matching the size decides, large downloads
+            // legitimately take longer; the "MAX_DOWNLOAD_TIME" applies when none of them matches.
+            let max_download_time_ms = scoped_max_download_time_ms.unwrap_or_else(|| {
+                env.max_download_time_buckets
+                    .iter()
+                    .flatten()
+                    .find(|(comparison, size, _)| match comparison {
+                        '<' => download.size < *size,
+                        _ => download.size > *size,
+                    })
+                    .map_or_else(
+                        || {
+                            utils::parse::string_time_notation_to_ms(&env.max_download_time)
+                                .unwrap() as u64
+                        },
+                        |(_, _, max_download_time_ms)| *max_download_time_ms,
+                    )
+            });
+            let max_age_ms = utils::parse::string_time_notation_to_ms(&env.max_age).unwrap() as u64;
 
-            if download.status == "metadata"
-                || download.eta >= max_download_time_ms
-                || (download.eta == 0 && download.status != "queued")
+            let error_message = download.messages.iter().find(|message| {
+                env.error_patterns
+                    .iter()
+                    .any(|pattern| pattern.is_match(message))
+            });
+
+            // Downloads of an indexer in "INDEXER_STRIKE" are striked every run, whatever their state.
+            let indexer_reason = format!("Indexer: {}", download.indexer);
+            let indexer_striked = env
+                .indexer_strike
+                .contains(&download.indexer.to_ascii_lowercase());
+
+            // Explicit stalled or warning states are striked, even when the ETA looks fine.
+            let strike_reason = if let Some(error_message) = error_message {
+                // The matched message itself explains the strike best.
+                Some(error_message.as_str())
+            } else if download.status == "metadata" {
+                Some("Fetching metadata")
+            } else if download.status == "stalled" && env.strike_on_stalled == "true" {
+                Some("Stalled")
+            } else if download.status == "warning"
+                || (download.tracked_status == "warning" && download.status == "downloading")
+            {
+                Some("Warning")
+            } else if env.zero_seeder_strikes > 0 && tracker.zero_seeders >= env.zero_seeder_strikes
+            {
+                // Torrents without seeders never finish, whatever their ETA predicts.
+                Some("No seeders")
+            } else if max_age_ms > 0 && download.age >= max_age_ms {
+                // Downloads that crawl along forever are striked, whatever their ETA predicts.
+                Some("Too old")
+            } else if eta >= max_download_time_ms {
+                Some(ETA_TOO_LONG)
+            } else if eta == 0
+                && download.status != "queued"
+                && env.strike_on_infinite_eta == "true"
+            {
+                Some("No ETA")
+            } else if progress == 0.0
+                && download.status != "queued"
+                && env.strike_on_zero_progress == "true"
             {
-                if strikes < env.max_strikes {
-                    strikes += 1;
-                    strikelist.insert(id, strikes);
+                Some("No progress")
+            } else if indexer_striked {
+                Some(indexer_reason.as_str())
+            } else {
+                None
+            };
+
+            // Strikes are given at most once every "STRIKE_INTERVAL", regardless of the scan interval.
+            let strike_interval_ms =
+                utils::parse::string_time_notation_to_ms(&env.strike_interval).unwrap() as u128;
+            let strike_due = tracker
+                .last_strike
+                .is_none_or(|last_strike| last_strike.elapsed().as_millis() >= strike_interval_ms);
+
+            // Every further "ESCALATION_FACTOR" times the ETA exceeds the "MAX_DOWNLOAD_TIME" adds a strike,
+            // e.g. with a factor of 3, an ETA of 10 times the limit gives 3 strikes in a single run.
+            let mut increment: u32 = 1;
+            if env.escalating_strikes == "true" && strike_reason == Some(ETA_TOO_LONG) {
+                let mut limit = max_download_time_ms.saturating_mul(env.escalation_factor);
+                while increment < MAX_ESCALATED_STRIKES && limit > 0 && eta >= limit {
+                    increment += 1;
+                    limit = limit.saturating_mul(env.escalation_factor);
+                }
+            }
+
+            if let Some(strike_reason) = strike_reason {
+                let striked = strikes < env.max_strikes && strike_due;
+                if striked {
+                    strikes = (strikes + increment).min(env.max_strikes);
+                    tracker.last_strike = Some(Instant::now());
+                    metrics::increment("swaparr_torrents_striked_total", instance);
+                    notify::striked(env, instance, &clients.services, &download, strikes);
+                    audit::record(env, instance, "striked", &download, strikes);
                 }
                 state = String::from("Striked");
+                if reason.is_empty() {
+                    reason = utils::parse::truncate_to_width(strike_reason, REASON_WIDTH);
+                }
+                if striked {
+                    utils::log::event(instance, "strike", &download, strikes, &reason);
+                }
+            } else if env.strike_decay == "true"
+                && !blacklisted
+                && strikes > 0
+                && tracker.eta.is_some_and(|eta| download.eta < eta)
+            {
+                // Downloads that are making progress again slowly shed their strikes, blacklisted
+                // ones are removed regardless.
+                strikes -= 1;
+                reason = String::from("Making progress");
+            }
+
+            // Removed in the same run the final strike is given, "MAX_STRIKES=3" removes a download
+            // on its 3rd qualifying run; a strike never carries over into an extra run.
+            // The first "WARMUP_CYCLES" runs never remove anything, the strikes still add up meanwhile.
+            if cycle.warmup && state == "Striked" {
+                state = String::from("Warmup");
             }
 
             if strikes >= env.max_strikes {
-                if env.dry_run == "false" {
-                    delete(&format!(
-                        "{}queue/{}?apikey={}&blocklist={}&removeFromClient={}",
-                        baseapi, id, env.apikey, true, env.remove_from_client
-                    ));
+                if cycle.warmup {
+                    state = String::from("Warmup");
+                } else if prowlarr::is_down(&cycle.indexers_down, &download.indexer) {
+                    // Removed once the indexer is back, so the starr instance can grab another release.
+                    state = String::from("Deferred");
+                    reason = utils::parse::truncate_to_width(
+                        &format!("Indexer down: {}", download.indexer),
+                        REASON_WIDTH,
+                    );
+                } else if !cycle.schedule() {
+                    // Once "MAX_REMOVALS_PER_CYCLE" is reached, the other downloads wait for the next run.
+                    state = String::from("Deferred");
+                } else if env.dry_run == "true" {
+                    if env.quiet == "true" {
+                        utils::log::alert(
+                            "INFO",
+                            "Download would have been removed, dry-run mode is enabled.",
+                            &download.name,
+                            None,
+                        );
+                    }
+                    state = String::from("Would Remove");
+                    would_remove.push((download.id, reason.clone(), download.size));
+                } else {
+                    // Removed after every download is processed, see the Removal Section.
+                    removals.push((table_contents.len(), download.clone(), strikes));
+                    state = String::from("Removed");
                 }
-                state = String::from("Removed");
             }
         }
 
+        tracker.strikes = strikes;
+        tracker.eta = Some(download.eta);
+
         // -- Logging Section
 
         table_contents.push(libs::table::TableContent {
             strikes: format!("{}/{}", strikes, env.max_strikes),
-            name: download.name.chars().take(32).collect::<String>(),
+            name: utils::parse::truncate_to_width(&download.name, env.name_width),
             eta: utils::parse::ms_to_eta_string(&download.eta),
-            size: format!("{:.2} GB", (download.size as f64 / 1000000000.0)).to_string(),
+            size: utils::parse::bytes_to_size_string(&download.size, &env.size_units),
+            progress: format!("{}%", progress.floor()),
             state,
+            reason,
         })
     }
 
-    libs::table::render(&table_contents);
+    // -- Removal Section: Removes up to "DELETE_CONCURRENCY" downloads at the same time.
+
+    let mut removed_downloads: Vec<(&Download, u32)> = vec![];
+    let mut removed_rows: Vec<usize> = vec![];
+    let mut reclaimed: u64 = 0;
+
+    for batch in removals.chunks(env.delete_concurrency) {
+        let results: Vec<bool> = thread::scope(|scope| {
+            let handles: Vec<_> = batch
+                .iter()
+                .map(|(_, download, _)| {
+                    scope.spawn(move || delete(env, instance, &clients.starr, &download.ids))
+                })
+                .collect();
+
+            // A panicking removal is treated as a failed one, the download is kept.
+            handles
+                .into_iter()
+                .zip(batch)
+                .map(|(handle, (_, download, _))| {
+                    handle.join().unwrap_or_else(|_| {
+                        utils::log::alert(
+                            "ERROR",
+                            "Removing the download panicked, will attempt again next run.",
+                            &download.name,
+                            None,
+                        );
+                        false
+                    })
+                })
+                .collect()
+        });
+
+        for ((row, download, strikes), removed) in batch.iter().zip(results) {
+            if !removed {
+                table_contents[*row].state = String::from("Failed");
+                continue;
+            }
+
+            // A download that lingers in the queue after its removal is only counted once.
+            if let Some(tracker) = strikelist.get_mut(&download.id) {
+                if !tracker.notified {
+                    tracker.notified = true;
+                    metrics::increment("swaparr_torrents_removed_total", instance);
+                    audit::record(env, instance, "removed", download, *strikes);
+
+                    if !removal_cooldown.is_zero() {
+                        REMOVED
+                            .lock()
+                            .unwrap_or_else(|error| error.into_inner())
+                            .extend(
+                                download.media_ids.iter().map(|media_id| {
+                                    (instance.id.clone(), *media_id, Instant::now())
+                                }),
+                            );
+                    }
+                    utils::log::event(
+                        instance,
+                        "remove",
+                        download,
+                        *strikes,
+                        &table_contents[*row].reason,
+                    );
+
+                    notify::removed(env, instance, &clients.services, download, *strikes);
+                    removed_downloads.push((download, *strikes));
+                    removed_rows.push(*row);
+                    metrics::add("swaparr_reclaimed_bytes_total", instance, download.size);
+                    reclaimed += download.size;
+                }
+            }
+
+            // Removals are the only thing logged in quiet mode, as the table is hidden.
+            if env.quiet == "true" {
+                utils::log::alert("INFO", "Download has been removed.", &download.name, None);
+            }
+        }
+    }
+
+    notify::removals(env, instance, &clients.services, &removed_downloads);
+
+    let count = |states: &[&str]| {
+        table_contents
+            .iter()
+            .filter(|content| states.contains(&content.state.as_str()))
+            .count()
+    };
+
+    Summary {
+        total: table_contents.len(),
+        striked: count(&["Striked", "Warmup"]),
+        removed: count(&["Removed", "Would Remove"]),
+        ignored: count(&["Ignored"]),
+        queued: count(&["Queued"]),
+        failed: false,
+        reclaimed,
+        table_contents,
+        removed_rows,
+        would_remove,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+
+    const GB: u64 = 1_000_000_000;
+
+    // A page of the Radarr queue, a healthy download and one that will take days to finish.
+    const RADARR_QUEUE: &str = r#"{"page": 1, "pageSize": 256, "totalRecords": 2, "records": [
+        {"id": 1, "size": 2000000000, "sizeleft": 1000000000, "timeleft": "00:10:00",
+         "status": "downloading", "trackedDownloadStatus": "ok", "trackedDownloadState": "downloading",
+         "protocol": "torrent", "downloadClient": "qBittorrent", "indexer": "Nyaa (Prowlarr)",
+         "downloadId": "AAA", "title": "Healthy.Movie.2020.1080p", "movie": {"title": "Healthy Movie"}},
+        {"id": 2, "size": 3000000000, "sizeleft": 3000000000, "timeleft": "5.00:00:00",
+         "status": "downloading", "trackedDownloadStatus": "ok", "trackedDownloadState": "downloading",
+         "protocol": "torrent", "downloadClient": "qBittorrent", "indexer": "1337x",
+         "downloadId": "BBB", "title": "Slow.Movie.2020.1080p", "movie": {"title": "Slow Movie"}}
+    ]}"#;
+
+    // A page of the Sonarr queue, a usenet download the starr instance warns about.
+    const SONARR_QUEUE: &str = r#"{"page": 1, "pageSize": 256, "totalRecords": 1, "records": [
+        {"id": 7, "size": 1500000000, "sizeleft": 500000000, "timeleft": "00:10:30.5",
+         "status": "warning", "trackedDownloadStatus": "warning", "trackedDownloadState": "downloading",
+         "errorMessage": "Unpacking failed", "protocol": "usenet", "downloadClient": "SABnzbd",
+         "indexer": "NZBgeek", "downloadId": "SABnzbd_nzo_1", "title": "Show.S01E01.1080p",
+         "series": {"title": "Show"}, "episode": {"title": "Pilot"},
+         "statusMessages": [{"title": "Show.S01E01.1080p", "messages": ["Missing articles"]}]}
+    ]}"#;
+
+    // A page of the Radarr queue, a completed download waiting to be imported.
+    const IMPORTING_QUEUE: &str = r#"{"page": 1, "pageSize": 256, "totalRecords": 1, "records": [
+        {"id": 4, "size": 500000000, "sizeleft": 0, "timeleft": "00:00:00",
+         "status": "completed", "trackedDownloadStatus": "warning", "trackedDownloadState": "importPending",
+         "statusMessages": [{"title": "No files found are eligible for import"}],
+         "protocol": "torrent", "downloadClient": "qBittorrent", "downloadId": "DDD",
+         "title": "Importing.Movie.2020.1080p", "movie": {"title": "Importing Movie"}}
+    ]}"#;
+
+    // The torrents of the Radarr queue as qBittorrent sees them, nobody seeds the slow one.
+    const TORRENTS: &str = r#"[
+        {"hash": "aaa", "state": "downloading", "eta": 600, "num_seeds": 4, "num_complete": 20},
+        {"hash": "bbb", "state": "downloading", "eta": 600, "num_seeds": 0, "num_complete": 0}
+    ]"#;
+
+    // A starr API serving a canned queue, recording every request as e.g. "DELETE /api/v3/queue/2?..".
+    // It answers for qBittorrent as well, in case "QBITTORRENT_URL" points at it.
+    struct Api {
+        baseurl: String,
+        // The body served for the queue, the API fails while there is none.
+        queue: Arc<Mutex<Option<String>>>,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Api {
+        fn serve(queue: &str) -> Api {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let api = Api {
+                baseurl: format!("http://{}", listener.local_addr().unwrap()),
+                queue: Arc::new(Mutex::new(Some(String::from(queue)))),
+                requests: Arc::new(Mutex::new(vec![])),
+            };
+
+            let (queue, requests) = (api.queue.clone(), api.requests.clone());
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                    let mut request = String::new();
+                    reader.read_line(&mut request).unwrap();
+                    let request = request.trim_end().trim_end_matches(" HTTP/1.1").to_string();
+
+                    // Headers are skipped, apart from the length of the body that follows them.
+                    let mut length = 0;
+                    loop {
+                        let mut header = String::new();
+                        reader.read_line(&mut header).unwrap();
+                        if header.trim().is_empty() {
+                            break;
+                        }
+                        if let Some(value) =
+                            header.to_ascii_lowercase().strip_prefix("content-length:")
+                        {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    reader.read_exact(&mut vec![0; length]).unwrap();
+
+                    let (status, body) = match request.split_once(' ') {
+                        Some(("GET", path)) if path.starts_with("/api/v2/torrents/info") => {
+                            ("200 OK", String::from(TORRENTS))
+                        }
+                        Some(("GET", path)) if path.contains("/queue") => {
+                            match queue.lock().unwrap().clone() {
+                                Some(queue) => ("200 OK", queue),
+                                None => ("500 Internal Server Error", String::from("{}")),
+                            }
+                        }
+                        Some(("DELETE", _)) => ("200 OK", String::new()),
+                        _ => ("404 Not Found", String::new()),
+                    };
+                    requests.lock().unwrap().push(request);
+
+                    write!(
+                        stream,
+                        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                    .unwrap();
+                }
+            });
+
+            api
+        }
+
+        fn instance(&self, platform: &str) -> utils::system::Instance {
+            utils::system::test_instance(platform, &self.baseurl)
+        }
+
+        fn deletes(&self) -> Vec<String> {
+            self.requests
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|request| request.starts_with("DELETE"))
+                .cloned()
+                .collect()
+        }
+    }
+
+    // Obtains and processes the queue once, as a single run does.
+    fn cycle(
+        env: &utils::system::Envs,
+        instance: &utils::system::Instance,
+        strikelist: &mut HashMap<u32, Tracker>,
+    ) -> Summary {
+        let clients = libs::http::clients(env);
+
+        run(
+            env,
+            instance,
+            &clients,
+            strikelist,
+            &Cycle::new(env, &clients, false),
+        )
+    }
+
+    fn states(summary: &Summary) -> Vec<(&str, &str)> {
+        summary
+            .table_contents
+            .iter()
+            .map(|row| (row.name.as_str(), row.state.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn get_reads_a_radarr_queue() {
+        let env = utils::system::test_env();
+        let api = Api::serve(RADARR_QUEUE);
+
+        let downloads = get(&env, &api.instance("radarr"), &libs::http::clients(&env)).unwrap();
+
+        assert_eq!(downloads.len(), 2);
+        assert_eq!(downloads[0].name, "Healthy Movie");
+        assert_eq!((downloads[0].size, downloads[0].sizeleft), (2 * GB, GB));
+        assert_eq!(downloads[0].eta, 600_000);
+        assert_eq!(downloads[0].download_id, "aaa");
+        assert_eq!(downloads[0].protocol, "torrent");
+        assert_eq!(downloads[0].download_client, "qbittorrent");
+        assert_eq!(downloads[0].indexer, "Nyaa (Prowlarr)");
+        assert_eq!(downloads[1].name, "Slow Movie");
+        assert_eq!(downloads[1].eta, 5 * 24 * 3_600_000);
+    }
+
+    #[test]
+    fn get_reads_a_sonarr_queue() {
+        let env = utils::system::test_env();
+        let api = Api::serve(SONARR_QUEUE);
+
+        let downloads = get(&env, &api.instance("sonarr"), &libs::http::clients(&env)).unwrap();
+
+        assert_eq!(downloads.len(), 1);
+        assert_eq!(downloads[0].name, "Show");
+        assert_eq!(downloads[0].eta, 630_500);
+        assert_eq!(downloads[0].status, "warning");
+        assert_eq!(downloads[0].protocol, "usenet");
+        assert_eq!(
+            downloads[0].messages,
+            vec!["Unpacking failed", "Show.S01E01.1080p", "Missing articles"]
+        );
+        assert!(api.requests.lock().unwrap()[0].contains("includeSeries=true"));
+    }
+
+    #[test]
+    fn get_fails_rather_than_returning_an_empty_queue() {
+        let env = utils::system::test_env();
+        let api = Api::serve(RADARR_QUEUE);
+        *api.queue.lock().unwrap() = None;
+
+        let queue = get(&env, &api.instance("radarr"), &libs::http::clients(&env));
+
+        assert!(matches!(queue, Err(QueueError::Response(_))));
+    }
+
+    #[test]
+    fn get_cross_references_qbittorrent() {
+        let mut env = utils::system::test_env();
+        let api = Api::serve(RADARR_QUEUE);
+        env.qbittorrent_url = api.baseurl.clone();
+
+        let downloads = get(&env, &api.instance("radarr"), &libs::http::clients(&env)).unwrap();
+
+        assert_eq!(downloads[0].seeders, Some(20));
+        assert_eq!(downloads[1].seeders, Some(0));
+        // The ETA of qBittorrent replaces the one of the queue.
+        assert_eq!(downloads[1].eta, 600_000);
+    }
+
+    #[test]
+    fn process_strikes_and_removes_as_configured() {
+        let mut env = utils::system::test_env();
+        env.max_strikes = 2;
+        let api = Api::serve(RADARR_QUEUE);
+        let instance = api.instance("radarr");
+        let mut strikelist = HashMap::new();
+
+        let summary = cycle(&env, &instance, &mut strikelist);
+        assert_eq!(
+            states(&summary),
+            vec![("Healthy Movie", "Normal"), ("Slow Movie", "Striked")]
+        );
+        assert_eq!(summary.table_contents[1].reason, "ETA too long");
+        assert!(api.deletes().is_empty());
+
+        let summary = cycle(&env, &instance, &mut strikelist);
+        assert_eq!(
+            states(&summary),
+            vec![("Healthy Movie", "Normal"), ("Slow Movie", "Removed")]
+        );
+        assert_eq!((summary.removed, summary.reclaimed), (1, 3 * GB));
+        assert_eq!(
+            api.deletes(),
+            vec![
+                "DELETE /api/v3/queue/2?blocklist=true&removeFromClient=true&skipRedownload=false"
+            ]
+        );
+    }
+
+    #[test]
+    fn removal_happens_on_the_run_of_the_final_strike() {
+        for max_strikes in 1..=3 {
+            let mut env = utils::system::test_env();
+            env.max_strikes = max_strikes;
+            let api = Api::serve(RADARR_QUEUE);
+            let instance = api.instance("radarr");
+            let mut strikelist = HashMap::new();
+
+            // "MAX_STRIKES=3" removes a download on its 3rd qualifying run, never on an extra one.
+            for run in 1..=max_strikes {
+                let summary = cycle(&env, &instance, &mut strikelist);
+                let row = &summary.table_contents[1];
+
+                if run < max_strikes {
+                    assert_eq!(row.state, "Striked", "MAX_STRIKES={max_strikes}, run {run}");
+                    assert!(api.deletes().is_empty());
+                } else {
+                    assert_eq!(row.state, "Removed", "MAX_STRIKES={max_strikes}, run {run}");
+                    assert_eq!(api.deletes().len(), 1);
+                }
+                assert_eq!(row.strikes, format!("{run}/{max_strikes}"));
+            }
+        }
+    }
+
+    #[test]
+    fn strikes_survive_a_failing_api() {
+        let env = utils::system::test_env();
+        let api = Api::serve(RADARR_QUEUE);
+        let instance = api.instance("radarr");
+        let mut strikelist = HashMap::new();
+
+        cycle(&env, &instance, &mut strikelist);
+        assert_eq!(strikelist[&2].strikes, 1);
+
+        *api.queue.lock().unwrap() = None;
+        let summary = cycle(&env, &instance, &mut strikelist);
+        assert!(summary.failed);
+        assert_eq!(strikelist.len(), 2);
+        assert_eq!(strikelist[&2].strikes, 1);
+
+        *api.queue.lock().unwrap() = Some(String::from(RADARR_QUEUE));
+        let summary = cycle(&env, &instance, &mut strikelist);
+        assert!(!summary.failed);
+        assert_eq!(strikelist[&2].strikes, 2);
+    }
+
+    #[test]
+    fn strikelist_shrinks_once_a_download_leaves_the_queue() {
+        let env = utils::system::test_env();
+        let api = Api::serve(RADARR_QUEUE);
+        let instance = api.instance("radarr");
+        let mut strikelist = HashMap::new();
+
+        cycle(&env, &instance, &mut strikelist);
+        assert_eq!(strikelist.len(), 2);
+
+        // The slow download finished, or was removed by hand.
+        *api.queue.lock().unwrap() = Some(RADARR_QUEUE.replacen("\"id\": 2", "\"id\": 3", 1));
+        cycle(&env, &instance, &mut strikelist);
+        assert_eq!(strikelist.len(), 2);
+        assert!(!strikelist.contains_key(&2));
+        assert_eq!(strikelist[&3].strikes, 1);
+
+        *api.queue.lock().unwrap() = Some(String::from(
+            r#"{"page": 1, "pageSize": 256, "totalRecords": 0, "records": []}"#,
+        ));
+        cycle(&env, &instance, &mut strikelist);
+        assert!(strikelist.is_empty());
+    }
+
+    #[test]
+    fn importing_downloads_are_left_alone() {
+        // Its ETA, warning and progress would all be striked otherwise.
+        let mut env = utils::system::test_env();
+        env.max_strikes = 1;
+        env.strike_on_infinite_eta = String::from("true");
+        env.strike_on_zero_progress = String::from("true");
+        env.error_patterns =
+            utils::parse::string_to_patterns("ERROR_PATTERNS", "eligible for import");
+        let api = Api::serve(IMPORTING_QUEUE);
+        let mut strikelist = HashMap::new();
+
+        let summary = cycle(&env, &api.instance("radarr"), &mut strikelist);
+
+        assert_eq!(states(&summary), vec![("Importing Movie", "Importing")]);
+        assert_eq!(summary.table_contents[0].reason, "Post-processing");
+        assert_eq!(strikelist[&4].strikes, 0);
+        assert!(api.deletes().is_empty());
+    }
+
+    #[test]
+    fn usenet_downloads_are_removed_through_the_same_endpoint() {
+        let mut env = utils::system::test_env();
+        env.max_strikes = 1;
+        env.protocol = String::from("usenet");
+        let api = Api::serve(SONARR_QUEUE);
+
+        let summary = cycle(&env, &api.instance("sonarr"), &mut HashMap::new());
+
+        assert_eq!(states(&summary), vec![("Show", "Removed")]);
+        assert_eq!(summary.table_contents[0].reason, "Warning");
+        // The job and its partial files are deleted from the usenet client, the NZB is blocklisted.
+        assert_eq!(
+            api.deletes(),
+            vec![
+                "DELETE /api/v3/queue/7?blocklist=true&removeFromClient=true&skipRedownload=false"
+            ]
+        );
+    }
+
+    #[test]
+    fn torrents_without_seeders_are_striked_after_some_runs() {
+        let mut env = utils::system::test_env();
+        env.zero_seeder_strikes = 2;
+        let api = Api::serve(RADARR_QUEUE);
+        env.qbittorrent_url = api.baseurl.clone();
+        let instance = api.instance("radarr");
+        let mut strikelist = HashMap::new();
+
+        let summary = cycle(&env, &instance, &mut strikelist);
+        assert_eq!(
+            states(&summary),
+            vec![("Healthy Movie", "Normal"), ("Slow Movie", "Normal")]
+        );
+
+        let summary = cycle(&env, &instance, &mut strikelist);
+        assert_eq!(
+            states(&summary),
+            vec![("Healthy Movie", "Normal"), ("Slow Movie", "Striked")]
+        );
+        assert_eq!(summary.table_contents[1].reason, "No seeders");
+    }
+
+    #[test]
+    fn siblings_of_a_removed_episode_are_no_regrab() {
+        let mut env = utils::system::test_env();
+        env.max_strikes = 1;
+        env.removal_cooldown = String::from("1h");
+        // A stalled episode of a series, Sonarr names every episode after the series.
+        let episode = |id: u32, episode_id: u32| {
+            format!(
+                r#"{{"page": 1, "pageSize": 256, "totalRecords": 1, "records": [
+                    {{"id": {id}, "size": 1000, "sizeleft": 1000, "timeleft": "5.00:00:00",
+                     "status": "downloading", "trackedDownloadState": "downloading",
+                     "protocol": "torrent", "episodeId": {episode_id}, "series": {{"title": "Sibling Show"}}}}
+                ]}}"#
+            )
+        };
+        let api = Api::serve(&episode(1, 4101));
+        let instance = api.instance("sonarr");
+        let mut strikelist = HashMap::new();
+
+        let summary = cycle(&env, &instance, &mut strikelist);
+        assert_eq!(states(&summary), vec![("Sibling Show", "Removed")]);
+
+        // The next episode of the same series is a download of its own.
+        *api.queue.lock().unwrap() = Some(episode(2, 4102));
+        let sibling = get(&env, &instance, &libs::http::clients(&env)).unwrap();
+        assert_eq!(sibling[0].media_ids, vec![4102]);
+        assert_eq!(regrabbed(&instance, &sibling[0]), None);
+
+        // Another release of the removed episode is the re-grab.
+        *api.queue.lock().unwrap() = Some(episode(3, 4101));
+        let regrab = get(&env, &instance, &libs::http::clients(&env)).unwrap();
+        assert!(regrabbed(&instance, &regrab[0]).is_some());
+    }
+
+    #[test]
+    fn lingering_downloads_are_counted_once() {
+        let mut env = utils::system::test_env();
+        env.max_strikes = 1;
+        let api = Api::serve(RADARR_QUEUE);
+        let instance = api.instance("radarr");
+        let mut strikelist = HashMap::new();
+
+        let summary = cycle(&env, &instance, &mut strikelist);
+        assert_eq!((summary.removed_rows, summary.reclaimed), (vec![1], 3 * GB));
+
+        // The starr instance still lists the download, its removal is attempted once more.
+        let summary = cycle(&env, &instance, &mut strikelist);
+        assert_eq!(states(&summary)[1], ("Slow Movie", "Removed"));
+        assert_eq!((summary.removed_rows, summary.reclaimed), (vec![], 0));
+        assert_eq!(api.deletes().len(), 2);
+    }
+
+    #[test]
+    fn name_width_leaves_the_reason_alone() {
+        let mut env = utils::system::test_env();
+        env.name_width = 4;
+        let api = Api::serve(RADARR_QUEUE);
+
+        let summary = cycle(&env, &api.instance("radarr"), &mut HashMap::new());
+
+        assert_eq!(summary.table_contents[1].name, "Slow");
+        assert_eq!(summary.table_contents[1].reason, "ETA too long");
+    }
+
+    #[test]
+    fn scoped_max_download_time_precedes_the_buckets() {
+        let mut env = utils::system::test_env();
+        env.max_download_time_buckets = Some(vec![('>', 0, 1)]);
+        let api = Api::serve(RADARR_QUEUE);
+        let mut instance = api.instance("radarr");
+
+        let summary = cycle(&env, &instance, &mut HashMap::new());
+        assert_eq!(states(&summary)[0], ("Healthy Movie", "Striked"));
+
+        instance.max_download_time = Some(String::from("1d"));
+        let summary = cycle(&env, &instance, &mut HashMap::new());
+        assert_eq!(states(&summary)[0], ("Healthy Movie", "Normal"));
+    }
+
+    #[test]
+    fn dry_run_never_removes() {
+        let mut env = utils::system::test_env();
+        env.max_strikes = 1;
+        env.dry_run = String::from("true");
+        let api = Api::serve(RADARR_QUEUE);
+
+        let summary = cycle(&env, &api.instance("radarr"), &mut HashMap::new());
+
+        assert_eq!(states(&summary)[1], ("Slow Movie", "Would Remove"));
+        assert_eq!(
+            summary.would_remove,
+            vec![(2, String::from("ETA too long"), 3 * GB)]
+        );
+        assert!(api.deletes().is_empty());
+    }
+
+    // A torrent halfway through, which none of the bypass rules match by default.
+    fn download(id: u32, name: &str) -> Download {
+        Download {
+            id,
+            ids: vec![id],
+            download_id: format!("HASH{id}"),
+            media_ids: vec![id],
+            name: String::from(name),
+            size: GB,
+            sizeleft: GB / 2,
+            status: String::from("downloading"),
+            tracked_status: String::from("ok"),
+            tracked_state: String::from("downloading"),
+            protocol: String::from("torrent"),
+            download_client: String::from("qbittorrent"),
+            indexer: String::from("Nyaa"),
+            messages: vec![],
+            eta: 60_000,
+            age: 0,
+            seeders: None,
+        }
+    }
+
+    // The bypass rules with the default sizes of 0 B up to 25 GB, seen long after any grace period.
+    fn bypassed(env: &utils::system::Envs, download: &Download, pardoned: bool) -> Bypass {
+        bypass(
+            env,
+            download,
+            pardoned,
+            25 * GB,
+            0,
+            Duration::from_secs(3600),
+        )
+    }
+
+    #[test]
+    fn bypass_rules_follow_their_precedence() {
+        let mut env = utils::system::test_env();
+        env.blacklist = utils::parse::string_to_patterns("BLACKLIST", "(?i)cam");
+        env.whitelist = utils::parse::string_to_patterns("WHITELIST", "(?i)keep");
+        let mut download = download(1, "Movie.CAM.keep");
+
+        assert_eq!(
+            bypassed(&env, &download, false),
+            Bypass::Skipped("Ignored", String::from("Whitelisted"))
+        );
+
+        // Pardons outrank every other rule.
+        assert_eq!(bypassed(&env, &download, true), Bypass::Pardoned);
+
+        // Sizes outrank the whitelist, the whitelist outranks the blacklist.
+        download.size = 30 * GB;
+        assert_eq!(
+            bypassed(&env, &download, false),
+            Bypass::Skipped("Ignored", String::from("Above size"))
+        );
+
+        download.size = GB;
+        download.name = String::from("Movie.CAM");
+        assert_eq!(bypassed(&env, &download, false), Bypass::Blacklisted);
+
+        // The blacklist outranks queued downloads and the grace period.
+        download.status = String::from("queued");
+        assert_eq!(bypassed(&env, &download, false), Bypass::Blacklisted);
+
+        download.name = String::from("Movie");
+        assert_eq!(
+            bypassed(&env, &download, false),
+            Bypass::Skipped("Queued", String::new())
+        );
+
+        download.status = String::from("downloading");
+        assert_eq!(bypassed(&env, &download, false), Bypass::Eligible);
+    }
+
+    #[test]
+    fn oversized_downloads_are_ignored_before_any_strike_rule() {
+        // "AGGRESSIVE_STRIKES" enables every strike rule, an infinite ETA would be striked.
+        let mut env = utils::system::test_env();
+        env.strike_on_infinite_eta = String::from("true");
+        env.strike_on_stalled = String::from("true");
+        env.strike_on_zero_progress = String::from("true");
+
+        let mut download = download(1, "Movie");
+        download.size = 30 * GB;
+        download.sizeleft = 30 * GB;
+        download.eta = 0;
+        download.status = String::from("stalled");
+
+        assert_eq!(
+            bypassed(&env, &download, false),
+            Bypass::Skipped("Ignored", String::from("Above size"))
+        );
+    }
+
+    // Processes a single run in dry-run mode, which never reaches out to the API.
+    fn dry_run(
+        env: &mut utils::system::Envs,
+        queue: Vec<Download>,
+        strikelist: &mut HashMap<u32, Tracker>,
+    ) -> Summary {
+        env.dry_run = String::from("true");
+        let instance = utils::system::test_instance("radarr", "http://127.0.0.1:9");
+        let clients = libs::http::clients(env);
+        let cycle = Cycle::new(env, &clients, false);
+
+        process(env, &instance, &clients, queue, strikelist, &cycle)
+    }
+
+    #[test]
+    fn blacklisted_downloads_do_not_decay() {
+        let mut env = utils::system::test_env();
+        env.strike_decay = String::from("true");
+        env.blacklist = utils::parse::string_to_patterns("BLACKLIST", "(?i)cam");
+
+        // Its ETA dropped since the last run, which would take a strike away.
+        let mut strikelist = HashMap::from([(
+            1,
+            Tracker {
+                eta: Some(120_000),
+                ..Default::default()
+            },
+        )]);
+
+        let summary = dry_run(&mut env, vec![download(1, "Movie.CAM")], &mut strikelist);
+
+        assert_eq!(strikelist[&1].strikes, env.max_strikes);
+        assert_eq!(summary.table_contents[0].state, "Would Remove");
+        assert_eq!(summary.table_contents[0].reason, "Blacklisted");
+    }
+
+    #[test]
+    fn protocol_limits_which_downloads_are_monitored() {
+        let mut env = utils::system::test_env();
+        let torrent = download(1, "Movie");
+        let mut usenet = download(2, "Show");
+        usenet.protocol = String::from("usenet");
+
+        assert_eq!(bypassed(&env, &torrent, false), Bypass::Eligible);
+        assert_eq!(bypassed(&env, &usenet, false), Bypass::Eligible);
+
+        env.protocol = String::from("torrent");
+        assert_eq!(bypassed(&env, &torrent, false), Bypass::Eligible);
+        assert_eq!(
+            bypassed(&env, &usenet, false),
+            Bypass::Skipped("Ignored", String::from("Protocol"))
+        );
+
+        env.protocol = String::from("usenet");
+        assert_eq!(
+            bypassed(&env, &torrent, false),
+            Bypass::Skipped("Ignored", String::from("Protocol"))
+        );
+        assert_eq!(bypassed(&env, &usenet, false), Bypass::Eligible);
+    }
+
+    #[test]
+    fn size_thresholds_are_inclusive_above_and_exclusive_below() {
+        let env = utils::system::test_env();
+        let mut download = download(1, "Movie");
+        let at = |download: &Download| {
+            bypass(
+                &env,
+                download,
+                false,
+                25 * GB,
+                GB,
+                Duration::from_secs(3600),
+            )
+        };
+
+        download.size = 25 * GB - 1;
+        assert_eq!(at(&download), Bypass::Eligible);
+        download.size = 25 * GB;
+        assert_eq!(
+            at(&download),
+            Bypass::Skipped("Ignored", String::from("Above size"))
+        );
+        download.size = 25 * GB + 1;
+        assert_eq!(
+            at(&download),
+            Bypass::Skipped("Ignored", String::from("Above size"))
+        );
+
+        download.size = GB - 1;
+        assert_eq!(
+            at(&download),
+            Bypass::Skipped("Ignored", String::from("Below size"))
+        );
+        download.size = GB;
+        assert_eq!(at(&download), Bypass::Eligible);
+        download.size = GB + 1;
+        assert_eq!(at(&download), Bypass::Eligible);
+    }
+
+    #[test]
+    fn grace_period_and_progress_leave_the_state_normal() {
+        let mut env = utils::system::test_env();
+        env.grace_period = String::from("10m");
+        let download = download(1, "Movie");
+
+        assert_eq!(
+            bypass(&env, &download, false, 25 * GB, 0, Duration::from_secs(60)),
+            Bypass::Skipped("Normal", String::from("Grace period"))
+        );
+        assert_eq!(bypassed(&env, &download, false), Bypass::Eligible);
+
+        env.min_progress = 50;
+        assert_eq!(
+            bypassed(&env, &download, false),
+            Bypass::Skipped("Normal", String::from("Above progress"))
+        );
+    }
+
+    #[test]
+    fn removal_budget_is_shared_by_every_instance() {
+        let mut env = utils::system::test_env();
+        env.max_removals_per_cycle = 3;
+        let cycle = Cycle::new(&env, &libs::http::clients(&env), false);
+
+        // Instances are processed concurrently, they take from the same budget.
+        let scheduled: u32 = thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| (0..2).filter(|_| cycle.schedule()).count() as u32))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .sum()
+        });
+
+        assert_eq!(scheduled, 3);
+        assert!(!cycle.schedule());
+    }
+
+    #[test]
+    fn zero_removals_per_cycle_is_unlimited() {
+        let env = utils::system::test_env();
+        let cycle = Cycle::new(&env, &libs::http::clients(&env), false);
 
-    if &env.dry_run == "true" {
-        println!(" ─ Dry-run mode enabled, no actions will be taken.");
+        assert!((0..1000).all(|_| cycle.schedule()));
     }
 }
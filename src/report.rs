@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use crate::{queue, utils};
+
+// Downloads that would have been removed in dry-run mode, kept until Swaparr exits.
+#[derive(Default)]
+pub struct Report {
+    // Instance id and download id mapped onto the reason and size, a download is counted once.
+    downloads: BTreeMap<(String, u32), (String, u64)>,
+}
+
+impl Report {
+    // Adds the downloads of a run that would have been removed.
+    pub fn add(&mut self, instance: &utils::system::Instance, summary: &queue::Summary) {
+        for (id, reason, size) in &summary.would_remove {
+            // Downloads at the maximum strikes without a current reason were striked in earlier runs.
+            let reason = if reason.is_empty() {
+                String::from("Striked earlier")
+            } else {
+                reason.clone()
+            };
+
+            self.downloads
+                .insert((instance.id.clone(), *id), (reason, *size));
+        }
+    }
+
+    // Prints what would have been removed, grouped by reason, along with the space it would free.
+    pub fn print(&self, env: &utils::system::Envs) {
+        if self.downloads.is_empty() {
+            utils::log::print(
+                " ─ Dry-run report: Swaparr would not have removed any downloads.\n\n",
+            );
+            return;
+        }
+
+        let mut reasons: BTreeMap<&str, (usize, u64)> = BTreeMap::new();
+        for (reason, size) in self.downloads.values() {
+            let (count, total) = reasons.entry(reason).or_default();
+            *count += 1;
+            *total += size;
+        }
+
+        let total: u64 = self.downloads.values().map(|(_, size)| size).sum();
+
+        utils::log::print(&format!(
+            " ─ Dry-run report: Swaparr would free {} across {} download(s).\n",
+            utils::parse::bytes_to_size_string(&total, &env.size_units),
+            self.downloads.len()
+        ));
+        for (reason, (count, size)) in reasons {
+            utils::log::print(&format!(
+                "   ─ {}: {} download(s), {}\n",
+                reason,
+                count,
+                utils::parse::bytes_to_size_string(&size, &env.size_units)
+            ));
+        }
+        utils::log::print("\n");
+    }
+}
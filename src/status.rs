@@ -0,0 +1,85 @@
+use std::{collections::HashMap, fs};
+
+use serde_json::{json, Map, Value};
+
+use crate::{queue, utils};
+
+// Checks the "STATUS_FILE" can be replaced, by creating and removing the temporary file next to it.
+pub fn init(env: &utils::system::Envs) {
+    if env.status_file.is_empty() {
+        return;
+    }
+
+    let temporary = format!("{}.tmp", env.status_file);
+    if let Err(error) = fs::write(&temporary, "").and_then(|_| fs::remove_file(&temporary)) {
+        utils::log::alert(
+            "FATAL",
+            "Environment variable \"STATUS_FILE\" is not valid.",
+            "It must be a path Swaparr is allowed to write to.",
+            Some(error.to_string()),
+        );
+        utils::system::exit(1);
+    }
+}
+
+// Replaces the "STATUS_FILE" with the outcome of the latest run, for external dashboards.
+pub fn write(
+    env: &utils::system::Envs,
+    summaries: &[queue::Summary],
+    strikelists: &[HashMap<u32, queue::Tracker>],
+    reclaimed: &[u64],
+) {
+    if env.status_file.is_empty() {
+        return;
+    }
+
+    let instances: Map<String, Value> = env
+        .instances
+        .iter()
+        .zip(summaries.iter().zip(strikelists).zip(reclaimed))
+        .map(|(instance, ((summary, strikelist), reclaimed))| {
+            // Number of downloads in every state, e.g. "Striked" or "Ignored".
+            let mut states: HashMap<&str, usize> = HashMap::new();
+            for content in &summary.table_contents {
+                *states.entry(content.state.as_str()).or_default() += 1;
+            }
+
+            // Download ids mapped onto their strikes, downloads without any are left out.
+            let strikelist: HashMap<String, u32> = strikelist
+                .iter()
+                .filter(|(_, tracker)| tracker.strikes > 0)
+                .map(|(id, tracker)| (id.to_string(), tracker.strikes))
+                .collect();
+
+            (
+                instance.id.clone(),
+                json!({
+                    "platform": instance.platform,
+                    "queue_size": summary.total,
+                    "states": states,
+                    "strikelist": strikelist,
+                    "reclaimed_bytes": reclaimed,
+                }),
+            )
+        })
+        .collect();
+
+    let status = json!({
+        "last_run": utils::log::timestamp(),
+        "instances": instances,
+    });
+
+    // Written next to the status file and renamed, readers never see a partial file.
+    let temporary = format!("{}.tmp", env.status_file);
+    let result = fs::write(&temporary, status.to_string())
+        .and_then(|_| fs::rename(&temporary, &env.status_file));
+
+    if let Err(error) = result {
+        utils::log::alert(
+            "WARN",
+            "Failed to write to the \"STATUS_FILE\".",
+            "Will attempt again next run.",
+            Some(error.to_string()),
+        );
+    }
+}
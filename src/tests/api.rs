@@ -1,29 +1,87 @@
-use reqwest::blocking::get;
-
-use crate::utils;
-
-pub fn test(platform: &str, baseapi: &str, apikey: &str) {
-    match get(&format!("{baseapi}health?apikey={apikey}")) {
-        Ok(res) => {
-            if res.status() != 200 {
-                utils::log::alert(
-                    "FATAL",
-                    "The provided \"APIKEY\" is not valid.",
-                    format!("Obtain the {platform} API key in Settings > General > API Key")
-                        .as_str(),
-                    None,
-                );
-                utils::system::exit(1);
+use std::{thread::sleep, time::Duration};
+
+use reqwest::blocking::Client;
+
+use crate::{health, libs, utils};
+
+pub fn test(env: &utils::system::Envs, instance: &utils::system::Instance, client: &Client) {
+    let platform = &instance.platform;
+    let url = format!("{}health", instance.baseapi);
+
+    let retry_delay = Duration::from_millis(
+        utils::parse::string_time_notation_to_ms(&env.health_retry_delay).unwrap_or(0) as u64,
+    );
+
+    // The starr instance may still be starting, e.g. when both containers start at once.
+    let mut attempt: u32 = 0;
+
+    loop {
+        let (title, message, detail, retryable) = match libs::http::send(libs::http::authorize(
+            env,
+            instance,
+            client.get(&url),
+        )) {
+            Ok(res) => {
+                let status = res.status();
+
+                // Each failure points at a different setting, rather than always blaming the API key.
+                let (title, message) = match status.as_u16() {
+                        200 => {
+                            health::report(instance, true);
+                            return;
+                        }
+                        401 | 403 => (
+                            String::from("The provided \"APIKEY\" is not valid."),
+                            format!("Obtain the {platform} API key in Settings > General > API Key, or check whether a proxy in front of {platform} forbids the request."),
+                        ),
+                        404 => (
+                            format!("The {platform} API could not be found at the provided \"BASEURL\"."),
+                            String::from("Ensure that \"BASEURL\", \"URL_BASE\" and \"API_VERSION\" point to the API of the instance."),
+                        ),
+                        500..=599 => (
+                            format!("The {platform} API has responded with a server error."),
+                            format!("Check the logs of {platform} and try again once it is running properly."),
+                        ),
+                        _ => (
+                            format!("The {platform} API has responded with an unexpected status."),
+                            String::from("Ensure that the API is accessible and try again."),
+                        ),
+                    };
+
+                // Waiting does not fix a wrong API key or URL, only a server that is not up yet.
+                (
+                    title,
+                    message,
+                    format!("The API has responded with status \"{status}\"."),
+                    status.is_server_error(),
+                )
             }
-        }
-        Err(error) => {
-            utils::log::alert(
-                "FATAL",
-                format!("A connection to the {platform} API could not be established.").as_str(),
-                "Ensure that the API is accessible and try again.",
-                Some(error.to_string()),
-            );
+            Err(error) => (
+                format!("A connection to the {platform} API could not be established."),
+                String::from("Ensure that the API is accessible and try again."),
+                error.to_string(),
+                true,
+            ),
+        };
+
+        if !retryable || attempt >= env.health_retries {
+            utils::log::alert("FATAL", &title, &message, Some(detail));
             utils::system::exit(1);
         }
+
+        attempt += 1;
+        utils::log::alert(
+            "WARN",
+            &title,
+            format!(
+                "Trying again in {} ({}/{}).",
+                humantime::format_duration(retry_delay),
+                attempt,
+                env.health_retries
+            )
+            .as_str(),
+            Some(detail),
+        );
+        sleep(retry_delay);
     }
 }
@@ -1,40 +1,306 @@
-use crate::utils;
+use std::{
+    fs::{self, OpenOptions},
+    io::{stderr, stdout, IsTerminal, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock,
+    },
+};
+
+use chrono::{Local, SecondsFormat};
+use serde_json::json;
+
+use crate::{queue, utils};
+
+// Severity of an alert, ordered so "LOG_LEVEL" hides everything below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl Level {
+    pub fn parse(level: &str) -> Option<Level> {
+        match level.to_ascii_uppercase().as_str() {
+            "INFO" => Some(Level::Info),
+            "WARN" => Some(Level::Warn),
+            "ERROR" => Some(Level::Error),
+            "FATAL" => Some(Level::Fatal),
+            _ => None,
+        }
+    }
+}
+
+// Logging preferences, set once the environment variables have been read.
+struct Settings {
+    level: Level,
+    format: String,
+    timestamps: bool,
+    color: bool,
+    file: String,
+    max_bytes: u64,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+// Instances log concurrently, writes to the "LOG_FILE" are done one at a time.
+static FILE: Mutex<()> = Mutex::new(());
+
+// Set by "EVENTS_STDOUT", stdout then only carries events and everything else goes to stderr.
+static EVENTS: AtomicBool = AtomicBool::new(false);
+
+// Routes the logs to stderr, as stdout is taken by the event stream.
+pub fn events(enabled: bool) {
+    EVENTS.store(enabled, Ordering::Relaxed);
+}
+
+// Prints human output, on stdout unless the event stream claimed it.
+pub fn print(text: &str) {
+    if EVENTS.load(Ordering::Relaxed) {
+        eprint!("{text}");
+    } else {
+        print!("{text}");
+    }
+}
+
+// Prints an event as one JSON object per line on stdout, meant to be piped into other tools.
+pub fn event(
+    instance: &utils::system::Instance,
+    event: &str,
+    download: &queue::Download,
+    strikes: u32,
+    reason: &str,
+) {
+    if !EVENTS.load(Ordering::Relaxed) {
+        return;
+    }
 
-pub fn empty() {
     println!(
-        "\n╭──────────────────────────────────────────────────────────────────────────╮\n│                            No downloads found                            │\n╰──────────────────────────────────────────────────────────────────────────╯\n",
+        "{}",
+        json!({
+            "event": event,
+            "timestamp": timestamp(),
+            "platform": instance.platform,
+            "instance": instance.id,
+            "id": download.id,
+            "name": download.name,
+            "size_bytes": download.size,
+            "eta_ms": download.eta,
+            "strikes": strikes,
+            "reason": reason,
+        })
+    );
+}
+
+// Applies the logging related environment variables.
+pub fn init(env: &utils::system::Envs) {
+    let _ = SETTINGS.set(Settings {
+        level: Level::parse(&env.log_level).unwrap_or(Level::Info),
+        format: env.log_format.clone(),
+        timestamps: env.log_timestamps == "true",
+        // Colors are only used when a terminal is attached, unless forced.
+        color: match env.color.as_str() {
+            "always" => true,
+            "never" => false,
+            _ if env.events_stdout == "true" => env.log_format == "text" && stderr().is_terminal(),
+            _ => env.log_format == "text" && stdout().is_terminal(),
+        },
+        file: env.log_file.clone(),
+        max_bytes: env.log_max_mb.saturating_mul(1000 * 1000),
+    });
+
+    // Created right away, a path Swaparr can't write to is reported before the first run.
+    if !env.log_file.is_empty() {
+        if let Err(error) = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&env.log_file)
+        {
+            alert(
+                "FATAL",
+                "Environment variable \"LOG_FILE\" is not valid.",
+                "It must be a path Swaparr is allowed to write to.",
+                Some(error.to_string()),
+            );
+            utils::system::exit(1);
+        }
+    }
+}
+
+// Appends to the "LOG_FILE", it is moved to "<LOG_FILE>.1" once it exceeds "LOG_MAX_MB".
+fn append(text: &str) {
+    let Some(settings) = SETTINGS.get().filter(|settings| !settings.file.is_empty()) else {
+        return;
+    };
+
+    let _lock = FILE.lock().unwrap_or_else(|error| error.into_inner());
+
+    if settings.max_bytes > 0
+        && fs::metadata(&settings.file)
+            .is_ok_and(|metadata| metadata.len() + text.len() as u64 > settings.max_bytes)
+    {
+        let _ = fs::rename(&settings.file, format!("{}.1", settings.file));
+    }
+
+    // A failing log file should never stop Swaparr, the logs are still on stdout (or stderr).
+    if let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&settings.file)
+    {
+        let _ = file.write_all(text.as_bytes());
+    }
+}
+
+// Wraps the text in an ANSI color code, if colors are enabled.
+pub fn paint(text: &str, code: u8) -> String {
+    if SETTINGS.get().is_some_and(|settings| settings.color) {
+        format!("\x1b[{code}m{text}\x1b[0m")
+    } else {
+        text.to_string()
+    }
+}
+
+// Colors a download state for the table, e.g. red for "Removed".
+pub fn state(state: &String) -> String {
+    match state.as_str() {
+        "Striked" | "Deferred" | "Warmup" => paint(state, 33),
+        "Removed" | "Would Remove" | "Failed" => paint(state, 31),
+        "Ignored" | "Queued" | "Importing" => paint(state, 90),
+        _ => state.to_string(),
+    }
+}
+
+// Returns the current time in ISO-8601, respects the "TZ" of the host.
+pub fn timestamp() -> String {
+    Local::now().to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+pub fn empty() {
+    print(
+        "\n╭──────────────────────────────────────────────────────────────────────────╮\n│                            No downloads found                            │\n╰──────────────────────────────────────────────────────────────────────────╯\n\n",
     );
 }
 
 pub fn alert(method: &str, title: &str, message: &str, error: Option<String>) {
-    println!("\n ─ {}", method);
-    println!("╭─╮ {}", title);
-    println!("╰─╯ {}", message);
+    let settings = SETTINGS.get();
 
-    if let Some(error) = error {
-        println!("{}", error);
+    // Alerts below the "LOG_LEVEL" are dropped, "FATAL" is the highest and always shown.
+    if settings.is_some_and(|settings| Level::parse(method).unwrap_or(Level::Info) < settings.level)
+    {
+        return;
     }
 
-    println!("\n");
+    // One object per line, for log collectors like Loki or ELK.
+
+    if settings.is_some_and(|settings| settings.format == "json") {
+        let line = json!({
+            "level": method,
+            "title": title,
+            "message": message,
+            "detail": error,
+            "timestamp": timestamp(),
+        });
+        print(&format!("{}\n", line));
+        append(&format!("{}\n", line));
+        return;
+    }
+
+    let level = match method {
+        "FATAL" | "ERROR" => paint(method, 31),
+        "WARN" => paint(method, 33),
+        _ => paint(method, 36),
+    };
+
+    let timestamp = if settings.is_none_or(|settings| settings.timestamps) {
+        format!("{} ", timestamp())
+    } else {
+        String::new()
+    };
+    let error = error
+        .map(|error| format!("{}\n", error))
+        .unwrap_or_default();
+
+    // The log file never contains colors, those would only clutter it.
+    let text =
+        |level: &str| format!("\n ─ {timestamp}{level}\n╭─╮ {title}\n╰─╯ {message}\n{error}\n\n");
+
+    print(&text(&level));
+    append(&text(method));
 }
 
 pub fn banner(env: &utils::system::Envs) {
-    // Yes, a lot of printlines, but it looks better like this.
-    println!("\n ── Swaparr ───── \n");
-    println!("╭─╮ Platform: {}", &env.platform);
-    println!("│ │ Max strikes: {}", &env.max_strikes);
-    println!("│ │ Scan interval: {}", env.scan_interval);
-    println!("│ │ Max download time: {}", &env.max_download_time);
-    println!("│ │ Ignore above size: {}", &env.ignore_above_size);
-    println!("╰─╯ Remove from client: {}\n", &env.remove_from_client);
+    // Yes, a lot of lines, but it looks better like this.
+    let mut banner = String::new();
+    let mut line = |text: &str| {
+        banner.push_str(text);
+        banner.push('\n');
+    };
+
+    line(&format!(
+        "\n ── Swaparr v{} ───── \n",
+        env!("CARGO_PKG_VERSION")
+    ));
+    for (index, instance) in env.instances.iter().enumerate() {
+        line(&format!(
+            "{} Instance: {} ─ {} {} at {}",
+            if index == 0 { "╭─╮" } else { "│ │" },
+            &instance.id,
+            &instance.platform,
+            &instance.api_version,
+            &instance.baseurl
+        ));
+    }
+    if env.instant_remove == "true" {
+        line(&format!(
+            "│ │ Max strikes: {} (instant remove)",
+            &env.max_strikes
+        ));
+    } else {
+        line(&format!("│ │ Max strikes: {}", &env.max_strikes));
+    }
+    line(&format!("│ │ Scan interval: {}", env.scan_interval));
+    line(&format!(
+        "│ │ Max download time: {}",
+        &env.max_download_time
+    ));
+    line(&format!(
+        "│ │ Ignore above size: {}",
+        &env.ignore_above_size
+    ));
+    line(&format!(
+        "│ │ Ignore below size: {}",
+        &env.ignore_below_size
+    ));
+    line(&format!(
+        "│ │ Remove from client: {}",
+        &env.remove_from_client
+    ));
+    line(&format!("│ │ Blocklist: {}", &env.blocklist));
+    line(&format!("╰─╯ Skip redownload: {}\n", &env.skip_redownload));
 
     if &env.dry_run == "true" {
-        println!("╭─╮ Dry-run: true");
-        println!("╰─╯ All destructive actions are negated.\n");
+        line("╭─╮ Dry-run: true");
+        line("╰─╯ All destructive actions are negated.\n");
     }
 
     // Open-Source = ❤️
-    println!("╭─╮ Be part of Swaparr's journey ⭐ Star us on GitHub!");
-    println!("╰─╯ Your support strengthens the open-source community.");
-    println!("\n ──────────────── \n")
+    line("╭─╮ Be part of Swaparr's journey ⭐ Star us on GitHub!");
+    line("╰─╯ Your support strengthens the open-source community.");
+    line("\n ──────────────── \n");
+
+    print(&banner);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_sits_between_warn_and_fatal() {
+        assert_eq!(Level::parse("error"), Some(Level::Error));
+        assert!(Level::Warn < Level::Error && Level::Error < Level::Fatal);
+    }
 }
@@ -2,94 +2,140 @@ use std::time::Duration;
 
 use bytesize::ByteSize;
 use humantime::format_duration;
+use regex::Regex;
+use unicode_width::UnicodeWidthChar;
 
 use crate::{queue, utils};
 
 // This will pretty-print an ETA from milliseconds.
 pub fn ms_to_eta_string(ms: &u64) -> String {
-    let eta = format_duration(Duration::from_millis(ms.clone())).to_string();
+    let eta = format_duration(Duration::from_millis(*ms)).to_string();
 
-    if format!("{eta}") == "0s" {
+    if eta == "0s" {
         String::from("Infinite")
     } else {
         eta
     }
 }
 
+// This will pretty-print a size from bytes in the most fitting unit, for example 1500000000 to
+// "1.5 GB" or "1.4 GiB" and 800000000 to "800.0 MB".
+pub fn bytes_to_size_string(bytes: &u64, units: &str) -> String {
+    let (base, prefixes) = match units {
+        "binary" => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB", "PiB"]),
+        _ => (1000.0, ["B", "KB", "MB", "GB", "TB", "PB"]),
+    };
+
+    // Scaled by hand, as "bytesize" renders exact powers like 1 GB as "1000.0 MB".
+    let mut size = *bytes as f64;
+    let mut prefix = 0;
+    while size >= base && prefix < prefixes.len() - 1 {
+        size /= base;
+        prefix += 1;
+    }
+
+    if prefix == 0 {
+        format!("{} {}", bytes, prefixes[0])
+    } else {
+        format!("{:.1} {}", size, prefixes[prefix])
+    }
+}
+
+// Shortens a name to the given display width, wide characters (e.g. CJK) take up two columns.
+pub fn truncate_to_width(string: &str, width: usize) -> String {
+    let mut used = 0;
+    string
+        .chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= width
+        })
+        .collect()
+}
+
 // Converts human-readable time notation to milliseconds.
-pub fn string_time_notation_to_ms(string: &String) -> Result<i64, ms_converter::Error> {
+pub fn string_time_notation_to_ms(string: &str) -> Result<i64, ms_converter::Error> {
     ms_converter::ms(string)
 }
 
 // This will convert for example "1 TB", "512 MB", <"1.5 GB" to 1500000 (bytes)>.
-pub fn string_bytesize_to_bytes(string: &String) -> Result<ByteSize, String> {
+// - Units are case-insensitive and whitespace is ignored, "1 gb" equals "1GB".
+// - With binary units "GB" is read as "GiB", so it matches the size shown in the table.
+pub fn string_bytesize_to_bytes(string: &str, units: &str) -> Result<ByteSize, String> {
+    let mut string = string.replace(char::is_whitespace, "");
+
+    if units == "binary" {
+        let unit = string.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+        if unit.len() == 2 && unit.to_ascii_lowercase().ends_with('b') {
+            string.insert(string.len() - 1, 'i');
+        }
+    }
+
     string.parse::<ByteSize>()
 }
 
 // Converts human-readable string (from Starr API) to milliseconds.
-pub fn string_hms_to_ms(string: &String) -> u64 {
-    let parts: Vec<&str> = string.split(|c| c == ':' || c == '.').collect();
+// The starr instances send a .NET TimeSpan: "hh:mm:ss" below a day, "d.hh:mm:ss" above it, e.g.
+// "7.12:30:00", so the hours never exceed 23 next to a day field. An "hh:mm:ss" with more hours,
+// e.g. "100:00:00", is still taken as is, and the day field has no upper limit.
+pub fn string_hms_to_ms(string: &str) -> u64 {
+    let parts: Vec<&str> = string.split(':').collect();
 
-    // Check if we have at least HH:MM:SS -> hours, minutes, and seconds
-    if parts.len() < 3 {
+    // Check if we have HH:MM:SS -> hours, minutes, and seconds
+    if parts.len() != 3 {
         return 0;
     }
 
-    let mut days: u64 = 0;
-    let hours: u64;
-    let minutes: u64;
-    let seconds: u64;
+    // Format-type "1.12:34:56", where the dot separates the days from the hours.
+    let (days, hours): (u64, u64) = match parts[0].split_once('.') {
+        Some((days, hours)) => (days.parse().unwrap_or(0), hours.parse().unwrap_or(0)),
+        None => (0, parts[0].parse().unwrap_or(0)),
+    };
+    let minutes: u64 = parts[1].parse().unwrap_or(0);
 
-    match parts.len() {
-        // Format-type "12:34:56"
-        3 => {
-            hours = parts[0].parse().unwrap_or_else(|_| 0);
-            minutes = parts[1].parse().unwrap_or_else(|_| 0);
-            seconds = parts[2].parse().unwrap_or_else(|_| 0);
-        }
-        // Format-type "12.34:56:78"
-        4 => {
-            days = parts[0].parse().unwrap_or_else(|_| 0);
-            hours = parts[1].parse().unwrap_or_else(|_| 0);
-            minutes = parts[2].parse().unwrap_or_else(|_| 0);
-            seconds = parts[3].parse().unwrap_or_else(|_| 0);
-        }
-        _ => return 0,
-    }
+    // Format-type "12:34:56.789", where the dot separates the fractional seconds.
+    let seconds: f64 = parts[2].parse().unwrap_or(0.0);
 
     // Calculate total milliseconds and return.
-    ((days * 24 + hours) * 3600 + minutes * 60 + seconds) * 1000
+    ((days * 24 + hours) * 3600 + minutes * 60) * 1000 + (seconds * 1000.0).round() as u64
 }
 
-// Returns the API version based on platform.
-pub fn baseapi(platform: &str, baseurl: &str) -> String {
-    match platform {
-        "radarr" => format!("{baseurl}/api/v3/"),
-        "sonarr" => format!("{baseurl}/api/v3/"),
-        "lidarr" => format!("{baseurl}/api/v1/"),
-        "readarr" => format!("{baseurl}/api/v1/"),
-        "whisparr" => format!("{baseurl}/api/v3/"),
-        _ => {
-            utils::log::alert(
-                "FATAL",
-                "Unknown \"PLATFORM\" value.",
-                "Either set it to \"radarr\", \"sonarr\", \"lidarr\", \"readarr\" or \"whisparr\".",
-                None,
-            );
-            utils::system::exit(1);
-        }
+// Returns the API base path, slashes are normalized so "http://host/" and "radarr/" join cleanly.
+// An "API_PATH" replaces both the "URL_BASE" and "/api/<API_VERSION>", it is used as is.
+pub fn baseapi(baseurl: &str, url_base: &str, api_version: &str, api_path: Option<&str>) -> String {
+    let baseapi = baseurl.trim_end_matches('/').to_string();
+
+    if let Some(api_path) = api_path {
+        let api_path = api_path.trim_matches('/');
+        return if api_path.is_empty() {
+            format!("{baseapi}/")
+        } else {
+            format!("{baseapi}/{api_path}/")
+        };
+    }
+
+    let url_base = url_base.trim_matches('/');
+    if url_base.is_empty() {
+        format!("{baseapi}/api/{api_version}/")
+    } else {
+        format!("{baseapi}/{url_base}/api/{api_version}/")
     }
 }
 
 // Returns the API endpoint based on platform.
-pub fn queueapi(platform: &str, baseapi: &str, apikey: &str) -> String {
-    let default_page_size = 256;
+pub fn queueapi(platform: &str, baseapi: &str, details: bool) -> String {
+    // The details endpoint returns the whole queue at once, the basic one is paginated.
+    let (endpoint, page_size) = if details {
+        ("queue/details", String::new())
+    } else {
+        ("queue", String::from("&pageSize=256"))
+    };
     match platform {
-        "radarr" => format!("{baseapi}queue?includeUnknownMovieItems=true&includeMovie=true&pageSize={default_page_size}&apikey={apikey}"),
-        "sonarr" => format!("{baseapi}queue?includeUnknownSeriesItems=true&includeSeries=true&pageSize={default_page_size}&apikey={apikey}"),
-        "lidarr" => format!("{baseapi}queue?includeUnknownArtistItems=true&includeArtist=true&includeAlbum=true&pageSize={default_page_size}&apikey={apikey}"),
-        "readarr" => format!("{baseapi}queue?includeUnknownAuthorItems=true&includeAuthor=true&includeBook=true&pageSize={default_page_size}&apikey={apikey}"),
-        "whisparr" => format!("{baseapi}queue?includeUnknownSeriesItems=true&includeSeries=true&includeEpisode=true&pageSize={default_page_size}&apikey={apikey}"),
+        "radarr" => format!("{baseapi}{endpoint}?includeUnknownMovieItems=true&includeMovie=true{page_size}"),
+        "sonarr" => format!("{baseapi}{endpoint}?includeUnknownSeriesItems=true&includeSeries=true{page_size}"),
+        "lidarr" => format!("{baseapi}{endpoint}?includeUnknownArtistItems=true&includeArtist=true&includeAlbum=true{page_size}"),
+        "readarr" => format!("{baseapi}{endpoint}?includeUnknownAuthorItems=true&includeAuthor=true&includeBook=true{page_size}"),
+        "whisparr" => format!("{baseapi}{endpoint}?includeUnknownSeriesItems=true&includeSeries=true&includeEpisode=true&includeMovie=true{page_size}"),
         _ => {
             utils::log::alert(
                 "FATAL",
@@ -102,35 +148,70 @@ pub fn queueapi(platform: &str, baseapi: &str, apikey: &str) -> String {
     }
 }
 
+// Returns the API endpoint to remove a download from the queue, the bulk one for a group of records
+// whose ids are sent in the body.
+pub fn deleteapi(
+    env: &utils::system::Envs,
+    instance: &utils::system::Instance,
+    ids: &[u32],
+) -> String {
+    let endpoint = match ids {
+        [id] => id.to_string(),
+        _ => String::from("bulk"),
+    };
+    format!(
+        "{}queue/{}?blocklist={}&removeFromClient={}&skipRedownload={}",
+        instance.baseapi, endpoint, env.blocklist, env.remove_from_client, env.skip_redownload
+    )
+}
+
 // This function extracts the name from a record based on the provided platform.
 pub fn recordname(platform: &str, record: &queue::Record) -> String {
-    let mut title: &str = match platform {
+    let title: &str = match platform {
         "radarr" => match record.movie.as_ref() {
             Some(movie) => &movie.title,
-            None => "Unknown",
+            None => "",
         },
         "sonarr" => match record.series.as_ref() {
             Some(series) => &series.title,
-            None => "Unknown",
+            None => "",
         },
-        "lidarr" => match record.album.as_ref() {
-            Some(album) => &album.title,
-            None => "Unknown",
+        // Prefer the album title, fall back onto the artist for unknown albums.
+        "lidarr" => match (record.album.as_ref(), record.artist.as_ref()) {
+            (Some(album), _) => &album.title,
+            (None, Some(artist)) => &artist.name,
+            (None, None) => "",
         },
-        "readarr" => match record.book.as_ref() {
-            Some(book) => &book.title,
-            None => "Unknown",
+        // Prefer the book title, fall back onto the author for unknown books.
+        "readarr" => match (record.book.as_ref(), record.author.as_ref()) {
+            (Some(book), _) => &book.title,
+            (None, Some(author)) => &author.name,
+            (None, None) => "",
         },
-        "whisparr" => match record.series.as_ref() {
-            Some(series) => &series.title,
-            None => "Unknown",
+        // Whisparr v2 nests scenes as episodes of a site (series), v3 as movies.
+        "whisparr" => match (
+            record.episode.as_ref(),
+            record.movie.as_ref(),
+            record.series.as_ref(),
+        ) {
+            (Some(scene), _, _) => &scene.title,
+            (None, Some(movie), _) => &movie.title,
+            (None, None, Some(site)) => &site.title,
+            (None, None, None) => "",
         },
-        _ => "Unknown",
+        _ => "",
     };
 
-    if title.is_empty() {
-        title = "Unknown"
-    }
+    // Records without a nested object often still carry the release title, or at least the hash.
+    let title = [
+        Some(title),
+        record.title.as_deref(),
+        record.downloadId.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|title| !title.is_empty())
+    .unwrap_or("Unknown");
 
     String::from(title)
 }
@@ -143,3 +224,182 @@ pub fn string_to_bool(string: String) -> Result<bool, String> {
         _ => Err(string),
     }
 }
+
+// Splits a "MAX_DOWNLOAD_TIME_BUCKETS" entry, e.g. "<10GB:1h", into its comparison, size in bytes
+// and time in milliseconds.
+pub fn string_to_bucket(string: &str, units: &str) -> Option<(char, u64, u64)> {
+    let (size, time) = string.split_once(':')?;
+    let size = size.trim();
+
+    let comparison = size.chars().next().filter(|c| *c == '<' || *c == '>')?;
+    let bytes = string_bytesize_to_bytes(&size[1..], units).ok()?.as_u64();
+    let ms = string_time_notation_to_ms(time.trim())
+        .ok()
+        .filter(|ms| *ms > 0)?;
+
+    Some((comparison, bytes, ms as u64))
+}
+
+// Splits a comma-separated string into a list of trimmed, lowercase values.
+pub fn string_to_list(string: &str) -> Vec<String> {
+    string
+        .split(',')
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+// Splits a comma-separated string of regex patterns, "\\," being a literal comma, e.g. "x{1\\,3}".
+fn split_patterns(string: &str) -> Vec<String> {
+    let mut patterns: Vec<String> = vec![];
+    let mut pattern = String::new();
+    let mut chars = string.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.next_if_eq(&',').is_some() => pattern.push(','),
+            ',' => patterns.push(std::mem::take(&mut pattern)),
+            _ => pattern.push(c),
+        }
+    }
+
+    patterns.push(pattern);
+    patterns
+}
+
+// Compiles a comma-separated string of regex patterns, exits on invalid patterns.
+pub fn string_to_patterns(which: &str, string: &str) -> Vec<Regex> {
+    split_patterns(string)
+        .iter()
+        .map(|pattern| pattern.trim())
+        .filter(|pattern| !pattern.is_empty())
+        .map(|pattern| match Regex::new(pattern) {
+            Ok(regex) => regex,
+            Err(error) => {
+                utils::log::alert(
+                    "FATAL",
+                    format!("Environment variable \"{which}\" is not valid.").as_str(),
+                    format!("The pattern \"{pattern}\" is not a valid regular expression.")
+                        .as_str(),
+                    Some(error.to_string()),
+                );
+                utils::system::exit(1);
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timespans_with_days_and_fractional_seconds() {
+        const HOUR: u64 = 3_600_000;
+
+        assert_eq!(string_hms_to_ms("1.05:30:00"), 29 * HOUR + HOUR / 2);
+        assert_eq!(string_hms_to_ms("00:10:30.5"), 630_500);
+        assert_eq!(string_hms_to_ms("100:00:00"), 100 * HOUR);
+        assert_eq!(
+            string_hms_to_ms("7.12:30:00"),
+            (7 * 24 + 12) * HOUR + HOUR / 2
+        );
+        assert_eq!(string_hms_to_ms("00:00:00"), 0);
+    }
+
+    fn bytes(string: &str, units: &str) -> Result<u64, String> {
+        string_bytesize_to_bytes(string, units).map(|size| size.as_u64())
+    }
+
+    #[test]
+    fn bytesizes_in_common_spellings() {
+        assert_eq!(bytes("1 gb", "decimal"), Ok(1_000_000_000));
+        assert_eq!(bytes("1GB", "decimal"), Ok(1_000_000_000));
+        assert_eq!(bytes("1.5 GB", "decimal"), Ok(1_500_000_000));
+        assert_eq!(bytes("512 MB", "decimal"), Ok(512_000_000));
+        assert_eq!(bytes(" 0 B ", "decimal"), Ok(0));
+        assert!(bytes("lots", "decimal").is_err());
+    }
+
+    #[test]
+    fn binary_units_read_gb_as_gib() {
+        assert_eq!(bytes("1GB", "binary"), Ok(1 << 30));
+        assert_eq!(bytes("1 gb", "binary"), Ok(1 << 30));
+        assert_eq!(bytes("1.5 GB", "binary"), Ok(3 << 29));
+        assert_eq!(bytes("1 GiB", "binary"), Ok(1 << 30));
+        assert_eq!(bytes("100 B", "binary"), Ok(100));
+    }
+
+    #[test]
+    fn baseapi_joins_cleanly_with_or_without_slashes() {
+        assert_eq!(
+            baseapi("http://host", "", "v3", None),
+            "http://host/api/v3/"
+        );
+        assert_eq!(
+            baseapi("http://host/", "", "v3", None),
+            "http://host/api/v3/"
+        );
+        assert_eq!(
+            baseapi("http://host/radarr/", "", "v3", None),
+            "http://host/radarr/api/v3/"
+        );
+
+        // "URL_BASE" joins the same way, whichever slashes it carries.
+        assert_eq!(
+            baseapi("http://host", "radarr", "v3", None),
+            "http://host/radarr/api/v3/"
+        );
+        assert_eq!(
+            baseapi("http://host/", "/radarr/", "v1", None),
+            "http://host/radarr/api/v1/"
+        );
+
+        // "API_PATH" replaces both, the API version included.
+        assert_eq!(
+            baseapi("http://host/", "/radarr", "v3", Some("/radarr-api/v3/")),
+            "http://host/radarr-api/v3/"
+        );
+    }
+
+    #[test]
+    fn deleteapi_follows_the_removal_settings() {
+        let mut env = utils::system::test_env();
+        let instance = utils::system::test_instance("radarr", "http://host/radarr/");
+
+        assert_eq!(
+            deleteapi(&env, &instance, &[12]),
+            "http://host/radarr/api/v3/queue/12?blocklist=true&removeFromClient=true&skipRedownload=false"
+        );
+
+        env.blocklist = String::from("false");
+        env.remove_from_client = String::from("false");
+        assert_eq!(
+            deleteapi(&env, &instance, &[12]),
+            "http://host/radarr/api/v3/queue/12?blocklist=false&removeFromClient=false&skipRedownload=false"
+        );
+
+        // A group of records goes to the bulk endpoint, its ids are sent in the body.
+        assert_eq!(
+            deleteapi(&env, &instance, &[12, 13]),
+            "http://host/radarr/api/v3/queue/bulk?blocklist=false&removeFromClient=false&skipRedownload=false"
+        );
+    }
+
+    #[test]
+    fn patterns_split_on_unescaped_commas() {
+        assert_eq!(split_patterns("a,b"), vec!["a", "b"]);
+        assert_eq!(split_patterns(r"x{1\,3},\d+"), vec!["x{1,3}", r"\d+"]);
+        assert_eq!(split_patterns(""), vec![""]);
+    }
+
+    #[test]
+    fn escaped_commas_reach_the_regex() {
+        let patterns = string_to_patterns("BLACKLIST", r"^x{1\,3}$, (?i)sample");
+
+        assert_eq!(patterns.len(), 2);
+        assert!(patterns[0].is_match("xxx"));
+        assert!(!patterns[0].is_match("xxxx"));
+        assert!(patterns[1].is_match("Movie.SAMPLE.mkv"));
+    }
+}
@@ -1,42 +1,756 @@
-use std::{env, process, thread::sleep, time::Duration};
+use std::{
+    collections::HashMap,
+    env, fs,
+    net::IpAddr,
+    process,
+    sync::{mpsc, OnceLock},
+    thread::sleep,
+    time::Duration,
+};
+
+use regex::Regex;
 
 use crate::utils;
 
-#[derive(Debug)]
-pub struct Envs {
+// The starr platforms Swaparr is able to manage.
+pub const PLATFORMS: [&str; 5] = ["radarr", "sonarr", "lidarr", "readarr", "whisparr"];
+
+#[derive(Debug, Clone)]
+pub struct Instance {
+    pub id: String,
+    pub platform: String,
     pub baseurl: String,
+    pub baseapi: String,
     pub apikey: String,
-    pub platform: String,
+    pub api_version: String,
+    pub max_download_time: Option<String>,
+    pub ignore_above_size: Option<String>,
+    pub ignore_below_size: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Envs {
+    pub instances: Vec<Instance>,
+    pub apikey_in_query: String,
+    pub use_queue_details: String,
+    pub group_by_download: String,
     pub max_strikes: u32,
+    pub instant_remove: String,
+    pub max_removals_per_cycle: u32,
+    pub warmup_cycles: u32,
+    pub strike_decay: String,
+    pub escalating_strikes: String,
+    pub escalation_factor: u64,
+    pub strike_on_infinite_eta: String,
+    pub strike_on_stalled: String,
+    pub strike_on_zero_progress: String,
+    pub zero_seeder_strikes: u32,
     pub scan_interval: String,
     pub max_download_time: String,
+    pub max_download_time_buckets: Option<Vec<(char, u64, u64)>>,
+    pub eta_window: usize,
+    pub grace_period: String,
+    pub max_age: String,
+    pub removal_cooldown: String,
+    pub strike_interval: String,
     pub ignore_above_size: String,
+    pub ignore_below_size: String,
+    pub size_units: String,
+    pub min_progress: u32,
+    pub protocol: String,
+    pub whitelist: Vec<Regex>,
+    pub blacklist: Vec<Regex>,
+    pub error_patterns: Vec<Regex>,
+    pub download_client_include: Vec<String>,
+    pub download_client_exclude: Vec<String>,
+    pub indexer_exclude: Vec<String>,
+    pub indexer_strike: Vec<String>,
+    pub qbittorrent_url: String,
+    pub qbittorrent_user: String,
+    pub qbittorrent_pass: String,
+    pub prowlarr_url: String,
+    pub prowlarr_apikey: String,
     pub remove_from_client: String,
+    pub blocklist: String,
+    pub skip_redownload: String,
     pub dry_run: String,
+    pub run_once: String,
+    pub quiet: String,
+    pub log_level: String,
+    pub log_format: String,
+    pub events_stdout: String,
+    pub log_timestamps: String,
+    pub log_file: String,
+    pub log_max_mb: u64,
+    pub audit_file: String,
+    pub audit_format: String,
+    pub audit_events: Vec<String>,
+    pub status_file: String,
+    pub pardon_file: String,
+    pub color: String,
+    pub name_width: usize,
+    pub request_timeout: u64,
+    pub user_agent: String,
+    pub insecure_skip_verify: String,
+    pub ca_cert_path: String,
+    pub health_retries: u32,
+    pub health_retry_delay: String,
+    pub delete_retries: u32,
+    pub delete_backoff_ms: u64,
+    pub delete_concurrency: usize,
+    pub discord_webhook: String,
+    pub telegram_bot_token: String,
+    pub telegram_chat_id: String,
+    pub slack_webhook: String,
+    pub pushover_token: String,
+    pub pushover_user: String,
+    // Ports, the priority and the address are "None" when set to something invalid, "problems()"
+    // reports those.
+    pub pushover_priority: Option<i8>,
+    pub ntfy_url: String,
+    pub ntfy_topic: String,
+    pub ntfy_token: String,
+    pub smtp_host: String,
+    pub smtp_port: Option<u16>,
+    pub smtp_user: String,
+    pub smtp_pass: String,
+    pub smtp_from: String,
+    pub smtp_to: Vec<String>,
+    pub webhook_url: String,
+    pub webhook_events: Vec<String>,
+    pub metrics_port: Option<u16>,
+    pub health_port: Option<u16>,
+    pub web_port: Option<u16>,
+    pub bind_address: Option<IpAddr>,
+}
+
+// Masks a secret down to its last 4 characters, e.g. "****cbc0".
+fn mask(secret: &str) -> String {
+    if secret.is_empty() {
+        return String::new();
+    }
+
+    let visible: String = secret
+        .chars()
+        .rev()
+        .take(4)
+        .collect::<Vec<char>>()
+        .into_iter()
+        .rev()
+        .collect();
+
+    format!("****{visible}")
+}
+
+impl Envs {
+    // The resolved configuration with every API key, token and password masked, for support requests.
+    pub fn redacted(&self) -> String {
+        let mut envs = self.clone();
+
+        for instance in &mut envs.instances {
+            instance.apikey = mask(&instance.apikey);
+        }
+
+        for secret in [
+            &mut envs.discord_webhook,
+            &mut envs.telegram_bot_token,
+            &mut envs.slack_webhook,
+            &mut envs.pushover_token,
+            &mut envs.pushover_user,
+            &mut envs.ntfy_token,
+            &mut envs.smtp_pass,
+            &mut envs.qbittorrent_pass,
+            &mut envs.prowlarr_apikey,
+            &mut envs.webhook_url,
+        ] {
+            *secret = mask(secret);
+        }
+
+        format!("{envs:#?}")
+    }
+
+    // Checks every setting at once, so all problems are reported in a single FATAL.
+    pub fn validate(&self) {
+        let problems = self.problems();
+
+        if !problems.is_empty() {
+            utils::log::alert(
+                "FATAL",
+                "Environment variables are not valid.",
+                format!(
+                    "Found {} problem(s), please correct the following:",
+                    problems.len()
+                )
+                .as_str(),
+                Some(problems.join("\n")),
+            );
+            exit(1);
+        }
+    }
+
+    // Every invalid setting, so they can all be reported at once.
+    fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = vec![];
+
+        for instance in &self.instances {
+            let prefix = if instance.id == instance.platform {
+                String::new()
+            } else {
+                format!("{}_", instance.id.to_ascii_uppercase())
+            };
+
+            if !PLATFORMS.contains(&instance.platform.as_str()) {
+                problems.push(format!(
+                    "\"{prefix}PLATFORM\" must be \"radarr\", \"sonarr\", \"lidarr\", \"readarr\" or \"whisparr\"."
+                ));
+            }
+
+            if !reqwest::Url::parse(&instance.baseurl)
+                .is_ok_and(|url| url.scheme() == "http" || url.scheme() == "https")
+            {
+                problems.push(format!(
+                    "\"{}\" must be a URL: \"http://127.0.0.1:7878\", etc..",
+                    if prefix.is_empty() {
+                        String::from("BASEURL")
+                    } else {
+                        format!("{prefix}URL")
+                    }
+                ));
+            }
+
+            if instance.apikey.trim().is_empty() {
+                problems.push(format!("\"{prefix}APIKEY\" must not be empty."));
+            }
+
+            // API versions are in the form of "v1", "v3", etc..
+            if !instance
+                .api_version
+                .strip_prefix('v')
+                .is_some_and(|version| {
+                    !version.is_empty() && version.chars().all(|c| c.is_ascii_digit())
+                })
+            {
+                problems.push(format!(
+                    "\"{prefix}API_VERSION\" must be an API version: \"v1\", \"v3\", etc.."
+                ));
+            }
+        }
+
+        if !["torrent", "usenet", "both"].contains(&self.protocol.as_str()) {
+            problems.push(String::from(
+                "\"PROTOCOL\" must be either \"torrent\", \"usenet\" or \"both\".",
+            ));
+        }
+
+        // Zero strikes would remove without a single strike, that is what "INSTANT_REMOVE" is for.
+        if self.max_strikes == 0 {
+            problems.push(String::from(
+                "\"MAX_STRIKES\" must be above zero, set \"INSTANT_REMOVE=true\" to remove on the first strike.",
+            ));
+        }
+
+        // A zero timeout would make reqwest give up on every request immediately.
+        if self.request_timeout == 0 {
+            problems.push(String::from(
+                "\"REQUEST_TIMEOUT\" must be a number of seconds above zero: \"30\", \"60\", etc..",
+            ));
+        }
+
+        if self.user_agent.trim().is_empty()
+            || reqwest::header::HeaderValue::from_str(&self.user_agent).is_err()
+        {
+            problems.push(String::from(
+                "\"USER_AGENT\" must be a user agent: \"swaparr/1.0\", etc..",
+            ));
+        }
+
+        if !self.qbittorrent_url.is_empty()
+            && !reqwest::Url::parse(&self.qbittorrent_url)
+                .is_ok_and(|url| url.scheme() == "http" || url.scheme() == "https")
+        {
+            problems.push(String::from(
+                "\"QBITTORRENT_URL\" must be a URL: \"http://127.0.0.1:8080\", etc..",
+            ));
+        }
+
+        // Only qBittorrent reports seeders, the queue of the starr instances does not.
+        if self.zero_seeder_strikes > 0 && self.qbittorrent_url.is_empty() {
+            problems.push(String::from(
+                "\"ZERO_SEEDER_STRIKES\" requires \"QBITTORRENT_URL\", the queue does not report seeders.",
+            ));
+        }
+
+        if !self.prowlarr_url.is_empty() {
+            if !reqwest::Url::parse(&self.prowlarr_url)
+                .is_ok_and(|url| url.scheme() == "http" || url.scheme() == "https")
+            {
+                problems.push(String::from(
+                    "\"PROWLARR_URL\" must be a URL: \"http://127.0.0.1:9696\", etc..",
+                ));
+            }
+
+            if self.prowlarr_apikey.trim().is_empty() {
+                problems.push(String::from(
+                    "\"PROWLARR_APIKEY\" must not be empty when \"PROWLARR_URL\" is set.",
+                ));
+            }
+        }
+
+        if utils::log::Level::parse(&self.log_level).is_none() {
+            problems.push(String::from(
+                "\"LOG_LEVEL\" must be a log level: \"info\", \"warn\", \"error\" or \"fatal\".",
+            ));
+        }
+
+        if self.log_format != "text" && self.log_format != "json" {
+            problems.push(String::from(
+                "\"LOG_FORMAT\" must be a log format: \"text\" or \"json\".",
+            ));
+        }
+
+        // Addresses are only checked when emails are enabled.
+        if !self.smtp_host.is_empty() && !self.smtp_to.is_empty() {
+            if self.smtp_from.parse::<lettre::message::Mailbox>().is_err() {
+                problems.push(String::from(
+                    "\"SMTP_FROM\" or \"SMTP_USER\" must be an email address: \"swaparr@example.com\", etc..",
+                ));
+            }
+
+            if self
+                .smtp_to
+                .iter()
+                .any(|to| to.parse::<lettre::message::Mailbox>().is_err())
+            {
+                problems.push(String::from(
+                    "\"SMTP_TO\" must be a list of email addresses: \"me@example.com\", etc..",
+                ));
+            }
+        }
+
+        if !self.audit_file.is_empty() {
+            if self.audit_format != "csv" && self.audit_format != "jsonl" {
+                problems.push(String::from(
+                    "\"AUDIT_FORMAT\" must be an audit format: \"csv\" or \"jsonl\".",
+                ));
+            }
+
+            if self
+                .audit_events
+                .iter()
+                .any(|event| event != "removed" && event != "striked")
+            {
+                problems.push(String::from(
+                    "\"AUDIT_EVENTS\" must be a list of events: \"removed\", \"striked\".",
+                ));
+            }
+        }
+
+        if !["auto", "always", "never"].contains(&self.color.as_str()) {
+            problems.push(String::from(
+                "\"COLOR\" must be either \"auto\", \"always\" or \"never\".",
+            ));
+        }
+
+        if self
+            .webhook_events
+            .iter()
+            .any(|event| event != "removed" && event != "striked")
+        {
+            problems.push(String::from(
+                "\"WEBHOOK_EVENTS\" must be a list of events: \"removed\", \"striked\".",
+            ));
+        }
+
+        if !utils::parse::string_time_notation_to_ms(&self.max_download_time)
+            .is_ok_and(|max_download_time_ms| max_download_time_ms > 0)
+        {
+            problems.push(String::from(
+                "\"MAX_DOWNLOAD_TIME\" must be a time-notation above zero: \"1d\", \"6h\", \"30m\", etc..",
+            ));
+        }
+
+        if self.max_download_time_buckets.is_none() {
+            problems.push(String::from(
+                "\"MAX_DOWNLOAD_TIME_BUCKETS\" must be a list of sizes and times: \"<10GB:1h\", \">50GB:12h\", etc..",
+            ));
+        }
+
+        if utils::parse::string_time_notation_to_ms(&self.grace_period)
+            .map_or(true, |grace_period_ms| grace_period_ms < 0)
+        {
+            problems.push(String::from(
+                "\"GRACE_PERIOD\" must be a time-notation: \"10m\", \"1h\", \"0s\", etc..",
+            ));
+        }
+
+        if utils::parse::string_time_notation_to_ms(&self.max_age)
+            .map_or(true, |max_age_ms| max_age_ms < 0)
+        {
+            problems.push(String::from(
+                "\"MAX_AGE\" must be a time-notation: \"1d\", \"12h\", \"0s\", etc..",
+            ));
+        }
+
+        if utils::parse::string_time_notation_to_ms(&self.removal_cooldown)
+            .map_or(true, |removal_cooldown_ms| removal_cooldown_ms < 0)
+        {
+            problems.push(String::from(
+                "\"REMOVAL_COOLDOWN\" must be a time-notation: \"1h\", \"30m\", \"0s\", etc..",
+            ));
+        }
+
+        if utils::parse::string_time_notation_to_ms(&self.strike_interval)
+            .map_or(true, |strike_interval_ms| strike_interval_ms < 0)
+        {
+            problems.push(String::from(
+                "\"STRIKE_INTERVAL\" must be a time-notation: \"30m\", \"1h\", \"0s\", etc..",
+            ));
+        }
+
+        if utils::parse::string_time_notation_to_ms(&self.health_retry_delay)
+            .map_or(true, |health_retry_delay_ms| health_retry_delay_ms < 0)
+        {
+            problems.push(String::from(
+                "\"HEALTH_RETRY_DELAY\" must be a time-notation: \"10s\", \"1m\", \"0s\", etc..",
+            ));
+        }
+
+        // Scanning more often than this would only hammer the API.
+        if !utils::parse::string_time_notation_to_ms(&self.scan_interval)
+            .is_ok_and(|scan_interval_ms| scan_interval_ms >= 5000)
+        {
+            problems.push(String::from(
+                "\"SCAN_INTERVAL\" must be a time-notation of at least \"5s\": \"10m\", \"1h\", etc..",
+            ));
+        }
+
+        if self.size_units != "decimal" && self.size_units != "binary" {
+            problems.push(String::from(
+                "\"SIZE_UNITS\" must be a unit system: \"decimal\" or \"binary\".",
+            ));
+        } else {
+            if !utils::parse::string_bytesize_to_bytes(&self.ignore_above_size, &self.size_units)
+                .is_ok_and(|size| size.as_u64() > 0)
+            {
+                problems.push(String::from(
+                    "\"IGNORE_ABOVE_SIZE\" must be a bytesize-notation above zero: \"1TB\", \"1GB\", \"1MB\", etc..",
+                ));
+            }
+
+            if utils::parse::string_bytesize_to_bytes(&self.ignore_below_size, &self.size_units)
+                .is_err()
+            {
+                problems.push(String::from(
+                    "\"IGNORE_BELOW_SIZE\" must be a bytesize-notation: \"1GB\", \"512MB\", \"0B\", etc..",
+                ));
+            }
+        }
+
+        // Scoped thresholds are shared by every instance of a platform, so checked once per platform.
+        for platform in PLATFORMS {
+            let Some(instance) = self
+                .instances
+                .iter()
+                .find(|instance| instance.platform == platform)
+            else {
+                continue;
+            };
+            let prefix = platform.to_ascii_uppercase();
+
+            if instance
+                .max_download_time
+                .as_ref()
+                .is_some_and(|max_download_time| {
+                    !utils::parse::string_time_notation_to_ms(max_download_time)
+                        .is_ok_and(|max_download_time_ms| max_download_time_ms > 0)
+                })
+            {
+                problems.push(format!(
+                    "\"{prefix}_MAX_DOWNLOAD_TIME\" must be a time-notation above zero: \"1d\", \"6h\", \"30m\", etc.."
+                ));
+            }
+
+            if instance
+                .ignore_above_size
+                .as_ref()
+                .is_some_and(|ignore_above_size| {
+                    !utils::parse::string_bytesize_to_bytes(ignore_above_size, &self.size_units)
+                        .is_ok_and(|size| size.as_u64() > 0)
+                })
+            {
+                problems.push(format!(
+                    "\"{prefix}_IGNORE_ABOVE_SIZE\" must be a bytesize-notation above zero: \"1TB\", \"1GB\", \"1MB\", etc.."
+                ));
+            }
+
+            if instance
+                .ignore_below_size
+                .as_ref()
+                .is_some_and(|ignore_below_size| {
+                    utils::parse::string_bytesize_to_bytes(ignore_below_size, &self.size_units)
+                        .is_err()
+                })
+            {
+                problems.push(format!(
+                    "\"{prefix}_IGNORE_BELOW_SIZE\" must be a bytesize-notation: \"1GB\", \"512MB\", \"0B\", etc.."
+                ));
+            }
+        }
+
+        for (which, port, examples) in [
+            ("SMTP_PORT", self.smtp_port, "\"465\", \"587\""),
+            ("METRICS_PORT", self.metrics_port, "\"9090\", \"9707\""),
+            ("HEALTH_PORT", self.health_port, "\"8080\", \"9708\""),
+            ("WEB_PORT", self.web_port, "\"8080\", \"9709\""),
+        ] {
+            if port.is_none() {
+                problems.push(format!(
+                    "\"{which}\" must be a port number: {examples}, etc.."
+                ));
+            }
+        }
+
+        if self.pushover_priority.is_none() {
+            problems.push(String::from(
+                "\"PUSHOVER_PRIORITY\" must be a priority from \"-2\" up to \"2\".",
+            ));
+        }
+
+        if self.bind_address.is_none() {
+            problems.push(String::from(
+                "\"BIND_ADDRESS\" must be an IP address: \"0.0.0.0\", \"127.0.0.1\", \"::\", etc..",
+            ));
+        }
+
+        problems
+    }
+}
+
+// Settings read from the "CONFIG_FILE", flattened into the same names as the env vars.
+static CONFIG: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+// Flattens a TOML table, e.g. "[radarr_1] url = .." becomes "RADARR_1_URL".
+fn flatten(prefix: &str, table: &toml::Table, config: &mut HashMap<String, String>) {
+    for (key, value) in table {
+        let key = format!("{prefix}{}", key.to_ascii_uppercase());
+        match value {
+            toml::Value::Table(table) => flatten(&format!("{key}_"), table, config),
+            toml::Value::String(value) => {
+                config.insert(key, value.clone());
+            }
+            // Lists are joined as comma-separated values, e.g. for "WHITELIST".
+            toml::Value::Array(values) => {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|value| match value {
+                        toml::Value::String(value) => value.clone(),
+                        value => value.to_string(),
+                    })
+                    .collect();
+                config.insert(key, values.join(","));
+            }
+            value => {
+                config.insert(key, value.to_string());
+            }
+        }
+    }
+}
+
+// Reads the optional "CONFIG_FILE", an empty config is used when undefined.
+fn config() -> HashMap<String, String> {
+    let mut config = HashMap::new();
+
+    let Ok(path) = env::var("CONFIG_FILE") else {
+        return config;
+    };
+
+    let table = fs::read_to_string(&path)
+        .map_err(|error| error.to_string())
+        .and_then(|contents| {
+            contents
+                .parse::<toml::Table>()
+                .map_err(|error| error.to_string())
+        })
+        .unwrap_or_else(|error| {
+            utils::log::alert(
+                "FATAL",
+                "Environment variable \"CONFIG_FILE\" is not valid.",
+                format!("Unable to read \"{path}\" as a TOML document.").as_str(),
+                Some(error),
+            );
+            exit(1);
+        });
+
+    flatten("", &table, &mut config);
+    config
+}
+
+// Returns a setting, env vars take precedence over the "CONFIG_FILE".
+fn var<K: AsRef<str>>(key: K) -> Result<String, env::VarError> {
+    env::var(key.as_ref()).or_else(|error| {
+        CONFIG
+            .get_or_init(config)
+            .get(key.as_ref())
+            .cloned()
+            .ok_or(error)
+    })
 }
 
 // Voids provided vars and returns a default value.
 fn default(which: &str, default: &str, invalid: bool) -> String {
-    println!(
-        " ─ ENV: \"{}\" is {}, using default: \"{}\".",
+    utils::log::print(&format!(
+        " ─ ENV: \"{}\" is {}, using default: \"{}\".\n",
         which,
         if invalid { "invalid" } else { "undefined" },
         default
-    );
+    ));
     default.to_string()
 }
 
+// A port number or the default when unset, "None" when it is set to anything else.
+fn port(which: &str, default: u16) -> Option<u16> {
+    var(which).map_or(Some(default), |port| port.parse::<u16>().ok())
+}
+
 // Delayed process exit, looks better on Windows if running without Docker.
 pub fn exit(code: i32) -> ! {
     sleep(Duration::from_secs(2));
     process::exit(code)
 }
 
-// Returns environment variables from the host.
-pub fn env() -> Envs {
-    let envs = Envs {
-        // ----- Unrecoverable -----
-        apikey: env::var("APIKEY").unwrap_or_else(|_| {
+// Listens for SIGINT and SIGTERM, the receiver gets a message once either arrives.
+pub fn shutdown() -> mpsc::Receiver<()> {
+    let (sender, receiver) = mpsc::channel();
+
+    if let Err(error) = ctrlc::set_handler(move || {
+        let _ = sender.send(());
+    }) {
+        utils::log::alert(
+            "WARN",
+            "Unable to listen for shutdown signals.",
+            "Swaparr will be stopped immediately instead of finishing its current run.",
+            Some(error.to_string()),
+        );
+    }
+
+    receiver
+}
+
+// Builds a starr instance, resolving its API version and path.
+fn instance(
+    id: String,
+    platform: String,
+    baseurl: String,
+    apikey: String,
+    api_version: Option<String>,
+    url_base: Option<String>,
+    api_path: Option<String>,
+) -> Instance {
+    let prefix = if id == platform {
+        String::new()
+    } else {
+        format!("{}_", id.to_ascii_uppercase())
+    };
+
+    // Lidarr and Readarr are served on the v1 API, the other platforms on v3.
+    let api_version = api_version.unwrap_or_else(|| match platform.as_str() {
+        "lidarr" | "readarr" => default(&format!("{prefix}API_VERSION"), "v1", false),
+        _ => default(&format!("{prefix}API_VERSION"), "v3", false),
+    });
+
+    // Thresholds scoped to the platform, e.g. "SONARR_MAX_DOWNLOAD_TIME", the global ones otherwise.
+    let scoped = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| var(format!("{}_{name}", platform.to_ascii_uppercase())).ok())
+    };
+
+    Instance {
+        // Instances served in a subfolder by a reverse proxy, e.g. "/radarr", or with a differently
+        // shaped path, e.g. "/radarr-api/v3", which replaces the whole API path instead.
+        baseapi: utils::parse::baseapi(
+            &baseurl,
+            url_base.as_deref().unwrap_or_default(),
+            &api_version,
+            api_path.as_deref(),
+        ),
+        max_download_time: scoped(&["MAX_DOWNLOAD_TIME", "TIME_THRESHOLD"]),
+        ignore_above_size: scoped(&["IGNORE_ABOVE_SIZE", "SIZE_THRESHOLD"]),
+        ignore_below_size: scoped(&["IGNORE_BELOW_SIZE", "MIN_SIZE_THRESHOLD"]),
+        id,
+        platform,
+        baseurl,
+        apikey,
+        api_version,
+    }
+}
+
+// Splits a setting into one value per listed platform, e.g. "URL_BASE=/radarr,/sonarr".
+fn per_platform(which: &str, value: Option<String>, platforms: usize) -> Vec<Option<String>> {
+    let Some(value) = value else {
+        return vec![None; platforms];
+    };
+
+    // Empty values leave a platform without one, e.g. "URL_BASE=,/sonarr".
+    let values: Vec<Option<String>> = value
+        .split(',')
+        .map(|value| Some(value.trim().to_string()).filter(|value| !value.is_empty()))
+        .collect();
+
+    if values.len() != platforms {
+        utils::log::alert(
+            "FATAL",
+            format!("ENV: \"{which}\" and \"PLATFORM\" do not have the same length.").as_str(),
+            format!(
+                "Found {platforms} platform(s) and {} value(s), list one for every platform, empty ones included.",
+                values.len()
+            )
+            .as_str(),
+            None,
+        );
+        exit(1);
+    }
+
+    values
+}
+
+// Returns the starr instances, either numbered (e.g. "RADARR_1_URL") or the single default one.
+fn instances() -> Vec<Instance> {
+    let mut instances: Vec<Instance> = vec![];
+
+    for platform in PLATFORMS {
+        let mut number = 1;
+
+        while let Ok(apikey) = var(format!("{}_{number}_APIKEY", platform.to_ascii_uppercase())) {
+            let id = format!("{platform}_{number}");
+            let prefix = id.to_ascii_uppercase();
+
+            let baseurl = var(format!("{prefix}_URL")).unwrap_or_else(|_| {
+                utils::log::alert(
+                    "FATAL",
+                    format!("ENV: \"{prefix}_URL\" is undefined and required.").as_str(),
+                    format!("Every \"{prefix}_APIKEY\" requires a matching URL.").as_str(),
+                    None,
+                );
+                exit(1);
+            });
+
+            instances.push(instance(
+                id,
+                platform.to_string(),
+                baseurl,
+                apikey,
+                var(format!("{prefix}_API_VERSION")).ok(),
+                var(format!("{prefix}_URL_BASE")).ok(),
+                var(format!("{prefix}_API_PATH")).ok(),
+            ));
+            number += 1;
+        }
+    }
+
+    // The single instance is optional once numbered instances are configured.
+    if instances.is_empty() || var("APIKEY").is_ok() {
+        let apikey = var("APIKEY").unwrap_or_else(|_| {
             utils::log::alert(
                 "FATAL",
                 "ENV: \"APIKEY\" is undefined and required.",
@@ -44,98 +758,684 @@ pub fn env() -> Envs {
                 None,
             );
             exit(1);
-        }),
+        });
+
+        let platform = var("PLATFORM").unwrap_or_else(|_| default("PLATFORM", "radarr", false));
+
+        let baseurl =
+            var("BASEURL").unwrap_or_else(|_| default("BASEURL", "http://127.0.0.1:7878", false));
+
+        // One setup may list several platforms, e.g. "PLATFORM=radarr,sonarr" with a URL and
+        // API key for each of them in the same order.
+        let split = |list: &str| -> Vec<String> {
+            list.split(',')
+                .map(|value| value.trim().to_string())
+                .collect()
+        };
+        let (platforms, baseurls, apikeys) = (split(&platform), split(&baseurl), split(&apikey));
+
+        if baseurls.len() != platforms.len() || apikeys.len() != platforms.len() {
+            utils::log::alert(
+                "FATAL",
+                "ENV: \"PLATFORM\", \"BASEURL\" and \"APIKEY\" do not have the same length.",
+                format!(
+                    "Found {} platform(s), {} URL(s) and {} API key(s), every platform requires its own.",
+                    platforms.len(),
+                    baseurls.len(),
+                    apikeys.len()
+                )
+                .as_str(),
+                None,
+            );
+            exit(1);
+        }
+
+        // The platform doubles as the id of the instance, so it has to be unique.
+        if let Some(platform) = platforms
+            .iter()
+            .enumerate()
+            .find_map(|(index, platform)| platforms[..index].contains(platform).then_some(platform))
+        {
+            utils::log::alert(
+                "FATAL",
+                format!("ENV: \"PLATFORM\" lists \"{platform}\" more than once.").as_str(),
+                "Use numbered instances for several instances of the same platform.",
+                None,
+            );
+            exit(1);
+        }
+
+        // A single "API_VERSION" applies to every listed platform.
+        let api_versions = var("API_VERSION")
+            .ok()
+            .map(|api_version| split(&api_version));
+
+        // Every listed platform is served under a path of its own, so these are never shared.
+        let url_bases = per_platform("URL_BASE", var("URL_BASE").ok(), platforms.len());
+        let api_paths = per_platform("API_PATH", var("API_PATH").ok(), platforms.len());
+
+        for (index, ((platform, baseurl), apikey)) in
+            platforms.into_iter().zip(baseurls).zip(apikeys).enumerate()
+        {
+            let api_version = api_versions.as_ref().and_then(|api_versions| {
+                if api_versions.len() == 1 {
+                    api_versions.first().cloned()
+                } else {
+                    api_versions.get(index).cloned()
+                }
+            });
+
+            instances.insert(
+                index,
+                instance(
+                    platform.clone(),
+                    platform,
+                    baseurl,
+                    apikey,
+                    api_version,
+                    url_bases[index].clone(),
+                    api_paths[index].clone(),
+                ),
+            );
+        }
+    }
+
+    instances
+}
+
+// Returns environment variables from the host.
+pub fn env() -> Envs {
+    // Decided first, so the notices below already end up on stderr when stdout carries events.
+    let events_stdout = if env::args().any(|arg| arg == "--events") {
+        String::from("true")
+    } else {
+        match utils::parse::string_to_bool(
+            var("EVENTS_STDOUT").unwrap_or_else(|_| default("EVENTS_STDOUT", "false", false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default("EVENTS_STDOUT", "false", true).to_string(),
+        }
+    };
+    utils::log::events(events_stdout == "true");
+
+    // The misspelled "AGGRESIVE_STRIKES" is still honored, when the correct spelling is unset.
+    let aggressive_strikes = var("AGGRESSIVE_STRIKES").or_else(|_| {
+        var("AGGRESIVE_STRIKES").inspect(|_| {
+            utils::log::alert(
+                "WARN",
+                "ENV: \"AGGRESIVE_STRIKES\" is deprecated.",
+                "Please rename it to \"AGGRESSIVE_STRIKES\", the old name will be removed.",
+                None,
+            );
+        })
+    });
+
+    // Enables every "STRIKE_ON_*" toggle at once, unless a toggle is set itself.
+    let aggressive_strikes =
+        utils::parse::string_to_bool(aggressive_strikes.unwrap_or_else(|_| String::from("false")))
+            .unwrap_or_else(|_| default("AGGRESSIVE_STRIKES", "false", true) == "true");
+    let toggle = |which: &str, enabled: &str| {
+        let enabled = if aggressive_strikes { "true" } else { enabled };
+        match utils::parse::string_to_bool(
+            var(which).unwrap_or_else(|_| default(which, enabled, false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default(which, enabled, true).to_string(),
+        }
+    };
+
+    // Decided ahead of the others, the "MAX_DOWNLOAD_TIME_BUCKETS" sizes are parsed in these units.
+    let size_units = var("SIZE_UNITS")
+        .unwrap_or_else(|_| default("SIZE_UNITS", "decimal", false))
+        .to_ascii_lowercase();
+
+    let mut envs = Envs {
+        // ----- Unrecoverable -----
+        instances: instances(),
 
         // ----- Recoverable via defaults -----
-        max_strikes: env::var("MAX_STRIKES")
+        apikey_in_query: match utils::parse::string_to_bool(
+            var("APIKEY_IN_QUERY").unwrap_or_else(|_| default("APIKEY_IN_QUERY", "false", false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default("APIKEY_IN_QUERY", "false", true).to_string(),
+        },
+
+        use_queue_details: match utils::parse::string_to_bool(
+            var("USE_QUEUE_DETAILS")
+                .unwrap_or_else(|_| default("USE_QUEUE_DETAILS", "false", false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default("USE_QUEUE_DETAILS", "false", true).to_string(),
+        },
+
+        group_by_download: match utils::parse::string_to_bool(
+            var("GROUP_BY_DOWNLOAD")
+                .unwrap_or_else(|_| default("GROUP_BY_DOWNLOAD", "false", false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default("GROUP_BY_DOWNLOAD", "false", true).to_string(),
+        },
+
+        max_strikes: var("MAX_STRIKES")
             // Allow falling back onto STRIKE_THRESHOLD for backwards compatibility.
-            .or_else(|_| env::var("STRIKE_THRESHOLD"))
+            .or_else(|_| var("STRIKE_THRESHOLD"))
             .unwrap_or_else(|_| default("MAX_STRIKES", "3", false))
-            // Convert to u32, if it fails, use default u32.
+            // Convert to u32, if it fails, use default u32. Zero is kept for "problems()" to report.
             .parse::<u32>()
             .unwrap_or_else(|_| {
                 default("MAX_STRIKES", "3", true);
                 3 // default
             }),
 
-        baseurl: env::var("BASEURL")
-            .unwrap_or_else(|_| default("BASEURL", "http://127.0.0.1:7878", false)),
+        instant_remove: match utils::parse::string_to_bool(
+            var("INSTANT_REMOVE").unwrap_or_else(|_| default("INSTANT_REMOVE", "false", false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default("INSTANT_REMOVE", "false", true).to_string(),
+        },
+
+        max_removals_per_cycle: var("MAX_REMOVALS_PER_CYCLE")
+            .unwrap_or_else(|_| default("MAX_REMOVALS_PER_CYCLE", "0", false))
+            // Convert to u32, if it fails, use default u32.
+            .parse::<u32>()
+            .unwrap_or_else(|_| {
+                default("MAX_REMOVALS_PER_CYCLE", "0", true);
+                0 // default
+            }),
+
+        warmup_cycles: var("WARMUP_CYCLES")
+            .unwrap_or_else(|_| default("WARMUP_CYCLES", "0", false))
+            // Convert to u32, if it fails, use default u32.
+            .parse::<u32>()
+            .unwrap_or_else(|_| {
+                default("WARMUP_CYCLES", "0", true);
+                0 // default
+            }),
+
+        strike_decay: match utils::parse::string_to_bool(
+            var("STRIKE_DECAY").unwrap_or_else(|_| default("STRIKE_DECAY", "false", false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default("STRIKE_DECAY", "false", true).to_string(),
+        },
+
+        escalating_strikes: match utils::parse::string_to_bool(
+            var("ESCALATING_STRIKES")
+                .unwrap_or_else(|_| default("ESCALATING_STRIKES", "false", false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default("ESCALATING_STRIKES", "false", true).to_string(),
+        },
+
+        escalation_factor: var("ESCALATION_FACTOR")
+            .unwrap_or_else(|_| default("ESCALATION_FACTOR", "3", false))
+            // Convert to u64, if it fails or is below 2, use default u64.
+            .parse::<u64>()
+            .ok()
+            .filter(|escalation_factor| *escalation_factor >= 2)
+            .unwrap_or_else(|| {
+                default("ESCALATION_FACTOR", "3", true);
+                3 // default
+            }),
+
+        strike_on_infinite_eta: toggle("STRIKE_ON_INFINITE_ETA", "true"),
+        strike_on_stalled: toggle("STRIKE_ON_STALLED", "true"),
+        strike_on_zero_progress: toggle("STRIKE_ON_ZERO_PROGRESS", "false"),
 
-        platform: env::var("PLATFORM").unwrap_or_else(|_| default("PLATFORM", "radarr", false)),
+        zero_seeder_strikes: var("ZERO_SEEDER_STRIKES")
+            .unwrap_or_else(|_| default("ZERO_SEEDER_STRIKES", "0", false))
+            // Convert to u32, if it fails, use default u32.
+            .parse::<u32>()
+            .unwrap_or_else(|_| {
+                default("ZERO_SEEDER_STRIKES", "0", true);
+                0 // default
+            }),
 
-        max_download_time: env::var("MAX_DOWNLOAD_TIME")
+        max_download_time: var("MAX_DOWNLOAD_TIME")
             // Allow falling back onto TIME_THRESHOLD for backwards compatibility.
-            .or_else(|_| env::var("TIME_THRESHOLD"))
+            .or_else(|_| var("TIME_THRESHOLD"))
             .unwrap_or_else(|_| default("MAX_DOWNLOAD_TIME", "2h", false)),
 
-        ignore_above_size: env::var("IGNORE_ABOVE_SIZE")
+        // Parsed once, "None" when any of them is invalid for "problems()" to report.
+        max_download_time_buckets: utils::parse::string_to_list(
+            &var("MAX_DOWNLOAD_TIME_BUCKETS").unwrap_or_default(),
+        )
+        .iter()
+        .map(|bucket| utils::parse::string_to_bucket(bucket, &size_units))
+        .collect(),
+
+        grace_period: var("GRACE_PERIOD").unwrap_or_else(|_| default("GRACE_PERIOD", "0s", false)),
+
+        max_age: var("MAX_AGE").unwrap_or_else(|_| default("MAX_AGE", "0s", false)),
+
+        removal_cooldown: var("REMOVAL_COOLDOWN")
+            .unwrap_or_else(|_| default("REMOVAL_COOLDOWN", "0s", false)),
+
+        eta_window: var("ETA_WINDOW")
+            .unwrap_or_else(|_| default("ETA_WINDOW", "1", false))
+            // Convert to usize, if it fails or is zero, use default usize.
+            .parse::<usize>()
+            .ok()
+            .filter(|eta_window| *eta_window > 0)
+            .unwrap_or_else(|| {
+                default("ETA_WINDOW", "1", true);
+                1 // default
+            }),
+
+        strike_interval: var("STRIKE_INTERVAL")
+            .unwrap_or_else(|_| default("STRIKE_INTERVAL", "0s", false)),
+
+        ignore_above_size: var("IGNORE_ABOVE_SIZE")
             // Allow falling back onto SIZE_THRESHOLD for backwards compatibility.
-            .or_else(|_| env::var("SIZE_THRESHOLD"))
+            .or_else(|_| var("SIZE_THRESHOLD"))
             .unwrap_or_else(|_| default("IGNORE_ABOVE_SIZE", "25 GB", false)),
 
-        scan_interval: env::var("SCAN_INTERVAL")
+        ignore_below_size: var("IGNORE_BELOW_SIZE")
+            // Allow falling back onto MIN_SIZE_THRESHOLD, mirroring SIZE_THRESHOLD.
+            .or_else(|_| var("MIN_SIZE_THRESHOLD"))
+            .unwrap_or_else(|_| default("IGNORE_BELOW_SIZE", "0 B", false)),
+
+        size_units,
+
+        min_progress: var("MIN_PROGRESS")
+            .unwrap_or_else(|_| default("MIN_PROGRESS", "0", false))
+            // Convert to u32, if it fails or exceeds 100%, use default u32.
+            .parse::<u32>()
+            .ok()
+            .filter(|min_progress| *min_progress <= 100)
+            .unwrap_or_else(|| {
+                default("MIN_PROGRESS", "0", true);
+                0 // default
+            }),
+
+        protocol: var("PROTOCOL")
+            .unwrap_or_else(|_| default("PROTOCOL", "both", false))
+            .to_ascii_lowercase(),
+
+        scan_interval: var("SCAN_INTERVAL")
             // Allow falling back onto CHECK_INTERVAL for backwards compatibility.
-            .or_else(|_| env::var("CHECK_INTERVAL"))
+            .or_else(|_| var("CHECK_INTERVAL"))
             .unwrap_or_else(|_| default("SCAN_INTERVAL", "10m", false)),
 
         remove_from_client: match utils::parse::string_to_bool(
-            env::var("REMOVE_FROM_CLIENT")
+            var("REMOVE_FROM_CLIENT")
                 .unwrap_or_else(|_| default("REMOVE_FROM_CLIENT", "true", false)),
         ) {
             Ok(value) => value.to_string(),
             Err(_) => default("REMOVE_FROM_CLIENT", "true", true).to_string(),
         },
 
+        blocklist: match utils::parse::string_to_bool(
+            var("BLOCKLIST").unwrap_or_else(|_| default("BLOCKLIST", "true", false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default("BLOCKLIST", "true", true).to_string(),
+        },
+
+        skip_redownload: match utils::parse::string_to_bool(
+            var("SKIP_REDOWNLOAD").unwrap_or_else(|_| default("SKIP_REDOWNLOAD", "false", false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default("SKIP_REDOWNLOAD", "false", true).to_string(),
+        },
+
         dry_run: match utils::parse::string_to_bool(
-            env::var("DRY_RUN")
-                .unwrap_or_else(|_| default("DRY_RUN", "false", false)),
+            var("DRY_RUN").unwrap_or_else(|_| default("DRY_RUN", "false", false)),
         ) {
             Ok(value) => value.to_string(),
             Err(_) => default("DRY_RUN", "false", true).to_string(),
         },
+
+        run_once: match utils::parse::string_to_bool(
+            var("RUN_ONCE").unwrap_or_else(|_| default("RUN_ONCE", "false", false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default("RUN_ONCE", "false", true).to_string(),
+        },
+
+        quiet: match utils::parse::string_to_bool(
+            var("QUIET").unwrap_or_else(|_| default("QUIET", "false", false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default("QUIET", "false", true).to_string(),
+        },
+
+        log_level: var("LOG_LEVEL")
+            .unwrap_or_else(|_| default("LOG_LEVEL", "info", false))
+            .to_ascii_lowercase(),
+
+        log_format: var("LOG_FORMAT")
+            .unwrap_or_else(|_| default("LOG_FORMAT", "text", false))
+            .to_ascii_lowercase(),
+
+        events_stdout,
+
+        log_timestamps: match utils::parse::string_to_bool(
+            var("LOG_TIMESTAMPS").unwrap_or_else(|_| default("LOG_TIMESTAMPS", "true", false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default("LOG_TIMESTAMPS", "true", true).to_string(),
+        },
+
+        log_max_mb: var("LOG_MAX_MB")
+            .unwrap_or_else(|_| default("LOG_MAX_MB", "10", false))
+            // Convert to u64, if it fails, use default u64.
+            .parse::<u64>()
+            .unwrap_or_else(|_| {
+                default("LOG_MAX_MB", "10", true);
+                10 // default
+            }),
+
+        color: var("COLOR")
+            .unwrap_or_else(|_| {
+                // Respects the "NO_COLOR" convention, see https://no-color.org.
+                if var("NO_COLOR").is_ok_and(|no_color| !no_color.is_empty()) {
+                    String::from("never")
+                } else {
+                    default("COLOR", "auto", false)
+                }
+            })
+            .to_ascii_lowercase(),
+
+        name_width: var("NAME_WIDTH")
+            .unwrap_or_else(|_| default("NAME_WIDTH", "32", false))
+            // Convert to usize, if it fails, use default usize.
+            .parse::<usize>()
+            .unwrap_or_else(|_| {
+                default("NAME_WIDTH", "32", true);
+                32 // default
+            }),
+
+        request_timeout: var("REQUEST_TIMEOUT")
+            .unwrap_or_else(|_| default("REQUEST_TIMEOUT", "30", false))
+            // Convert to u64, if it fails, use default u64.
+            .parse::<u64>()
+            .unwrap_or_else(|_| {
+                default("REQUEST_TIMEOUT", "30", true);
+                30 // default
+            }),
+
+        user_agent: var("USER_AGENT").unwrap_or_else(|_| {
+            default(
+                "USER_AGENT",
+                &format!("swaparr/{}", env!("CARGO_PKG_VERSION")),
+                false,
+            )
+        }),
+
+        insecure_skip_verify: match utils::parse::string_to_bool(
+            var("INSECURE_SKIP_VERIFY")
+                .unwrap_or_else(|_| default("INSECURE_SKIP_VERIFY", "false", false)),
+        ) {
+            Ok(value) => value.to_string(),
+            Err(_) => default("INSECURE_SKIP_VERIFY", "false", true).to_string(),
+        },
+
+        health_retries: var("HEALTH_RETRIES")
+            .unwrap_or_else(|_| default("HEALTH_RETRIES", "3", false))
+            // Convert to u32, if it fails, use default u32.
+            .parse::<u32>()
+            .unwrap_or_else(|_| {
+                default("HEALTH_RETRIES", "3", true);
+                3 // default
+            }),
+
+        health_retry_delay: var("HEALTH_RETRY_DELAY")
+            .unwrap_or_else(|_| default("HEALTH_RETRY_DELAY", "10s", false)),
+
+        delete_retries: var("DELETE_RETRIES")
+            .unwrap_or_else(|_| default("DELETE_RETRIES", "3", false))
+            // Convert to u32, if it fails, use default u32.
+            .parse::<u32>()
+            .unwrap_or_else(|_| {
+                default("DELETE_RETRIES", "3", true);
+                3 // default
+            }),
+
+        delete_backoff_ms: var("DELETE_BACKOFF_MS")
+            .unwrap_or_else(|_| default("DELETE_BACKOFF_MS", "1000", false))
+            // Convert to u64, if it fails, use default u64.
+            .parse::<u64>()
+            .unwrap_or_else(|_| {
+                default("DELETE_BACKOFF_MS", "1000", true);
+                1000 // default
+            }),
+
+        delete_concurrency: var("DELETE_CONCURRENCY")
+            .unwrap_or_else(|_| default("DELETE_CONCURRENCY", "4", false))
+            // Convert to usize, if it fails or is zero, use default usize.
+            .parse::<usize>()
+            .ok()
+            .filter(|delete_concurrency| *delete_concurrency > 0)
+            .unwrap_or_else(|| {
+                default("DELETE_CONCURRENCY", "4", true);
+                4 // default
+            }),
+
+        // ----- Optional, disabled when undefined -----
+        whitelist: utils::parse::string_to_patterns(
+            "WHITELIST",
+            &var("WHITELIST").unwrap_or_default(),
+        ),
+        blacklist: utils::parse::string_to_patterns(
+            "BLACKLIST",
+            &var("BLACKLIST").unwrap_or_default(),
+        ),
+        error_patterns: utils::parse::string_to_patterns(
+            "ERROR_PATTERNS",
+            &var("ERROR_PATTERNS").unwrap_or_default(),
+        ),
+        download_client_include: utils::parse::string_to_list(
+            &var("DOWNLOAD_CLIENT_INCLUDE").unwrap_or_default(),
+        ),
+        download_client_exclude: utils::parse::string_to_list(
+            &var("DOWNLOAD_CLIENT_EXCLUDE").unwrap_or_default(),
+        ),
+        indexer_exclude: utils::parse::string_to_list(&var("INDEXER_EXCLUDE").unwrap_or_default()),
+        indexer_strike: utils::parse::string_to_list(&var("INDEXER_STRIKE").unwrap_or_default()),
+        qbittorrent_url: var("QBITTORRENT_URL").unwrap_or_default(),
+        qbittorrent_user: var("QBITTORRENT_USER").unwrap_or_default(),
+        qbittorrent_pass: var("QBITTORRENT_PASS").unwrap_or_default(),
+        prowlarr_url: var("PROWLARR_URL").unwrap_or_default(),
+        prowlarr_apikey: var("PROWLARR_APIKEY").unwrap_or_default(),
+
+        log_file: var("LOG_FILE").unwrap_or_default(),
+        ca_cert_path: var("CA_CERT_PATH").unwrap_or_default(),
+
+        audit_file: var("AUDIT_FILE").unwrap_or_default(),
+        audit_format: var("AUDIT_FORMAT")
+            .unwrap_or_else(|_| String::from("csv"))
+            .to_ascii_lowercase(),
+        audit_events: utils::parse::string_to_list(
+            &var("AUDIT_EVENTS").unwrap_or_else(|_| String::from("removed")),
+        ),
+        status_file: var("STATUS_FILE").unwrap_or_default(),
+        pardon_file: var("PARDON_FILE").unwrap_or_default(),
+
+        discord_webhook: var("DISCORD_WEBHOOK").unwrap_or_default(),
+        telegram_bot_token: var("TELEGRAM_BOT_TOKEN").unwrap_or_default(),
+        telegram_chat_id: var("TELEGRAM_CHAT_ID").unwrap_or_default(),
+        slack_webhook: var("SLACK_WEBHOOK").unwrap_or_default(),
+        pushover_token: var("PUSHOVER_TOKEN").unwrap_or_default(),
+        pushover_user: var("PUSHOVER_USER").unwrap_or_default(),
+        // Pushover priorities range from -2 (lowest) up to 2 (emergency).
+        pushover_priority: var("PUSHOVER_PRIORITY").map_or(Some(0), |priority| {
+            priority
+                .parse::<i8>()
+                .ok()
+                .filter(|priority| (-2..=2).contains(priority))
+        }),
+        ntfy_url: var("NTFY_URL").unwrap_or_default(),
+        ntfy_topic: var("NTFY_TOPIC").unwrap_or_default(),
+        ntfy_token: var("NTFY_TOKEN").unwrap_or_default(),
+        smtp_host: var("SMTP_HOST").unwrap_or_default(),
+        smtp_port: port("SMTP_PORT", 587),
+        smtp_user: var("SMTP_USER").unwrap_or_default(),
+        smtp_pass: var("SMTP_PASS").unwrap_or_default(),
+        // The sender falls back onto the login, as most providers require them to match.
+        smtp_from: var("SMTP_FROM")
+            .or_else(|_| var("SMTP_USER"))
+            .unwrap_or_default(),
+        smtp_to: utils::parse::string_to_list(&var("SMTP_TO").unwrap_or_default()),
+        webhook_url: var("WEBHOOK_URL").unwrap_or_default(),
+        webhook_events: utils::parse::string_to_list(
+            &var("WEBHOOK_EVENTS").unwrap_or_else(|_| String::from("removed")),
+        ),
+
+        metrics_port: port("METRICS_PORT", 0),
+        health_port: port("HEALTH_PORT", 0),
+        web_port: port("WEB_PORT", 0),
+
+        // IPv4 or IPv6, e.g. "127.0.0.1" or "::1" to only accept local connections.
+        bind_address: var("BIND_ADDRESS").map_or(Some(IpAddr::from([0, 0, 0, 0])), |address| {
+            address.parse::<IpAddr>().ok()
+        }),
     };
 
-    // Check if variable MAX_DOWNLOAD_TIME is able to be parsed.
-    match utils::parse::string_time_notation_to_ms(&envs.max_download_time) {
-        Ok(_) => (),
-        Err(_) => {
-            utils::log::alert(
-                "FATAL",
-                "Environment variable \"MAX_DOWNLOAD_TIME\" is not valid.",
-                "Must be a time-notation: \"1d\", \"6h\", \"30m\", etc.. by default: \"2h\"",
-                None,
-            );
-            utils::system::exit(1);
-        }
+    // Instant removal is a single strike, the first qualifying run removes the download.
+    if envs.instant_remove == "true" {
+        envs.max_strikes = 1;
     }
 
-    // Check if variable IGNORE_ABOVE_SIZE is able to be parsed.
-    match utils::parse::string_bytesize_to_bytes(&envs.ignore_above_size) {
-        Ok(_) => (),
-        Err(_) => {
-            utils::log::alert(
-                "FATAL",
-                "Environment variable \"IGNORE_ABOVE_SIZE\" is not valid.",
-                "Must be a bytesize-notation: \"1TB\", \"1GB\", \"1MB\", etc.. by default: \"25GB\"",
-                None,
-            );
-            utils::system::exit(1);
-        }
+    utils::log::init(&envs);
+
+    // Telegram notifications require both a bot token and a chat id.
+    if envs.telegram_bot_token.is_empty() != envs.telegram_chat_id.is_empty() {
+        utils::log::alert(
+            "WARN",
+            "Telegram notifications are disabled.",
+            "Both \"TELEGRAM_BOT_TOKEN\" and \"TELEGRAM_CHAT_ID\" have to be set.",
+            None,
+        );
     }
 
-    // Check if variable SCAN_INTERVAL is able to be parsed.
-    match utils::parse::string_time_notation_to_ms(&envs.scan_interval) {
-        Ok(_) => (),
-        Err(_) => {
-            utils::log::alert(
-                "FATAL",
-                "Environment variable \"SCAN_INTERVAL\" is not valid.",
-                "Must be a time-notation: \"1d\", \"6h\", \"30m\", etc.. by default: \"10m\"",
-                None,
-            );
-            utils::system::exit(1);
-        }
+    // Pushover notifications require both an application token and a user key.
+    if envs.pushover_token.is_empty() != envs.pushover_user.is_empty() {
+        utils::log::alert(
+            "WARN",
+            "Pushover notifications are disabled.",
+            "Both \"PUSHOVER_TOKEN\" and \"PUSHOVER_USER\" have to be set.",
+            None,
+        );
+    }
+
+    envs.validate();
+
+    envs
+}
+
+// Settings as read from an otherwise empty environment, shared by the tests of every module.
+#[cfg(test)]
+pub fn test_env() -> Envs {
+    static ENVS: OnceLock<Envs> = OnceLock::new();
+
+    ENVS.get_or_init(|| {
+        env::set_var("APIKEY", "test");
+        env()
+    })
+    .clone()
+}
+
+// A single instance of the platform, as if set through "PLATFORM" and "BASEURL".
+#[cfg(test)]
+pub fn test_instance(platform: &str, baseurl: &str) -> Instance {
+    instance(
+        platform.to_string(),
+        platform.to_string(),
+        baseurl.to_string(),
+        String::from("test"),
+        None,
+        None,
+        None,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(test_env().problems().is_empty());
+    }
+
+    #[test]
+    fn invalid_url_names_the_setting() {
+        let mut env = test_env();
+        env.instances = vec![test_instance("radarr", "127.0.0.1:7878")];
+        assert!(env.problems()[0].starts_with("\"BASEURL\" must be a URL"));
+
+        let mut instance = test_instance("radarr", "127.0.0.1:7878");
+        instance.id = String::from("radarr_1");
+        env.instances = vec![instance];
+        assert!(env.problems()[0].starts_with("\"RADARR_1_URL\" must be a URL"));
+    }
+
+    #[test]
+    fn url_bases_are_split_per_platform() {
+        assert_eq!(per_platform("URL_BASE", None, 2), vec![None, None]);
+        assert_eq!(
+            per_platform("URL_BASE", Some(String::from("/radarr, /sonarr")), 2),
+            vec![Some(String::from("/radarr")), Some(String::from("/sonarr"))]
+        );
+        assert_eq!(
+            per_platform("API_PATH", Some(String::from(",/sonarr-api")), 2),
+            vec![None, Some(String::from("/sonarr-api"))]
+        );
+    }
+
+    #[test]
+    fn zero_seeder_strikes_require_qbittorrent() {
+        let mut env = test_env();
+        env.zero_seeder_strikes = 2;
+        assert!(env.problems()[0].starts_with("\"ZERO_SEEDER_STRIKES\" requires"));
+
+        env.qbittorrent_url = String::from("http://127.0.0.1:8080");
+        assert!(env.problems().is_empty());
     }
 
-    return envs;
+    #[test]
+    fn zero_request_timeout_is_rejected() {
+        let mut env = test_env();
+        env.request_timeout = 0;
+        assert!(env.problems()[0].starts_with("\"REQUEST_TIMEOUT\" must be"));
+
+        env.request_timeout = 1;
+        assert!(env.problems().is_empty());
+    }
+
+    #[test]
+    fn invalid_ports_and_addresses_are_rejected() {
+        let mut env = test_env();
+        env.web_port = None;
+        env.pushover_priority = None;
+        env.bind_address = None;
+
+        let problems = env.problems();
+        assert!(problems[0].starts_with("\"WEB_PORT\" must be"));
+        assert!(problems[1].starts_with("\"PUSHOVER_PRIORITY\" must be"));
+        assert!(problems[2].starts_with("\"BIND_ADDRESS\" must be"));
+    }
+
+    #[test]
+    fn problems_leave_the_files_alone() {
+        let mut env = test_env();
+        let path = env::temp_dir().join("swaparr-problems.log");
+        env.log_file = path.display().to_string();
+        env.audit_file = env.log_file.clone();
+
+        assert!(env.problems().is_empty());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn zero_max_strikes_points_to_instant_remove() {
+        let mut env = test_env();
+        env.max_strikes = 0;
+        assert!(env.problems()[0].contains("\"INSTANT_REMOVE=true\""));
+
+        env.max_strikes = 3;
+        assert!(env.problems().is_empty());
+    }
 }